@@ -0,0 +1,304 @@
+use bitvec::prelude::*;
+use hal::digital::OutputPin;
+use hal_async::delay::DelayNs;
+
+use crate::charset::Charset;
+use crate::init::{FIRST_WAKE_US, POWER_ON_US, WAKE_US};
+use crate::instr::*;
+use crate::utils::State;
+use crate::{
+    hal, hal_async, DataBus, EnablePin, LcdError, LcdPins, ReadWritePin, ADDRESS_SETUP_NS,
+};
+
+/// The async counterpart of [`Interface`](crate::Interface), awaiting [`DelayNs`] instead of
+/// blocking or returning [`nb::Error::WouldBlock`].
+#[allow(async_fn_in_trait)]
+pub trait AsyncInterface {
+    type Error;
+
+    const DATA_LENGTH: DataLength;
+
+    /// Writes a single nibble to DB4–DB7 as an instruction in one transfer, without checking
+    /// the busy flag.
+    async fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayNs,
+        nibble: u8,
+    ) -> Result<(), Self::Error>;
+
+    /// Waits until the controller has executed the previous transfer, then writes
+    /// `deliverable`.
+    async fn write(
+        &mut self,
+        delay: &mut impl DelayNs,
+        deliverable: Deliverable,
+    ) -> Result<(), Self::Error>;
+
+    /// Directs DDRAM transfers to one of several controllers, see
+    /// [`Interface::select`](crate::Interface::select).
+    #[inline]
+    fn select(&mut self, _controller: u8) {}
+}
+
+/// An [`AsyncInterface`] able to read back the busy flag and address counter.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadInterface: AsyncInterface {
+    async fn state(&mut self, delay: &mut impl DelayNs) -> Result<State, Self::Error>;
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> LcdPins<RS, RW, E, DB> {
+    /// Interval between two reads of the busy flag.
+    const BUSY_POLL_US: u32 = 10;
+
+    async fn pulse_enable_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.raise_enable_async(delay).await?;
+        self.lower_enable_async(delay).await
+    }
+
+    async fn raise_enable_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        delay.delay_ns(ADDRESS_SETUP_NS).await;
+        self.enable
+            .set_high()
+            .map_err(|e| LcdError::EnableError(e))?;
+        delay.delay_us(1).await;
+        Ok(())
+    }
+
+    async fn lower_enable_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.enable
+            .set_low()
+            .map_err(|e| LcdError::EnableError(e))?;
+        delay.delay_us(1).await;
+        Ok(())
+    }
+
+    async fn write_bits_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+        bits: &BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.put_bits(bits)?;
+        self.pulse_enable_async(delay).await
+    }
+
+    async fn read_bits_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+        bits: &mut BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.raise_enable_async(delay).await?;
+        self.take_bits(bits)?;
+        self.lower_enable_async(delay).await
+    }
+
+    async fn read_state_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<State, LcdError<RS, RW, E, DB>> {
+        self.begin_read()?;
+        let mut bits = bitarr!(u8, Lsb0; 0; 8);
+        match DB::DATA_LENGTH {
+            DataLength::Eight => self.read_bits_async(delay, &mut bits[..]).await?,
+            DataLength::Four => {
+                self.read_bits_async(delay, &mut bits[4..]).await?;
+                self.read_bits_async(delay, &mut bits[..4]).await?;
+            }
+        }
+        self.end_read()?;
+        Ok(State(bits.load::<u8>()))
+    }
+
+    async fn write_datum_async(
+        &mut self,
+        delay: &mut impl DelayNs,
+        deliverable: Deliverable,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        let datum = self.begin_write(deliverable)?;
+        match DB::DATA_LENGTH {
+            DataLength::Eight => {
+                self.write_bits_async(delay, datum.view_bits::<Lsb0>())
+                    .await
+            }
+            DataLength::Four => {
+                let (lower_bits, upper_bits) = datum.view_bits::<Lsb0>().split_at(4);
+                self.write_bits_async(delay, upper_bits).await?;
+                self.write_bits_async(delay, lower_bits).await
+            }
+        }
+    }
+
+    async fn wait_ready(
+        &mut self,
+        delay: &mut impl DelayNs,
+        target: Option<u8>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        if RW::READABLE {
+            for controller in Self::controllers(target) {
+                self.enable.select(Some(controller));
+                while self.read_state_async(delay).await?.busy() {
+                    delay.delay_us(Self::BUSY_POLL_US).await;
+                }
+            }
+        } else {
+            delay.delay_us(self.controller.countdown.take()).await;
+        }
+        Ok(())
+    }
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> AsyncInterface
+    for LcdPins<RS, RW, E, DB>
+{
+    type Error = LcdError<RS, RW, E, DB>;
+
+    const DATA_LENGTH: DataLength = DB::DATA_LENGTH;
+
+    async fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayNs,
+        nibble: u8,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.enable.select(None);
+        let datum = self.begin_write(Deliverable::Instr(CompiledInstr(nibble << 4)))?;
+        match DB::DATA_LENGTH {
+            DataLength::Eight => {
+                self.write_bits_async(delay, datum.view_bits::<Lsb0>())
+                    .await
+            }
+            DataLength::Four => {
+                self.write_bits_async(delay, &datum.view_bits::<Lsb0>()[4..])
+                    .await
+            }
+        }
+    }
+
+    async fn write(
+        &mut self,
+        delay: &mut impl DelayNs,
+        deliverable: Deliverable,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        for phase in self.controller.plan(deliverable).into_iter().flatten() {
+            self.wait_ready(delay, phase.target()).await?;
+            let mut i = 0;
+            while let Some((controller, datum)) = self.transfer(phase, i) {
+                self.enable.select(controller);
+                self.write_datum_async(delay, datum).await?;
+                i += 1;
+            }
+            self.complete(phase);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn select(&mut self, controller: u8) {
+        crate::Interface::select(self, controller)
+    }
+}
+
+impl<RS: OutputPin, RW: OutputPin, E: EnablePin, DB: DataBus> AsyncReadInterface
+    for LcdPins<RS, RW, E, DB>
+{
+    #[inline]
+    async fn state(&mut self, delay: &mut impl DelayNs) -> Result<State, Self::Error> {
+        self.enable.select(Some(crate::Interface::selected(self)));
+        self.read_state_async(delay).await
+    }
+}
+
+/// The async counterpart of [`Lcd`](crate::Lcd), for executors such as Embassy.
+pub struct AsyncLcd<I: AsyncInterface, D: DelayNs> {
+    interface: I,
+    delay: D,
+    charset: Charset,
+}
+
+impl<I: AsyncInterface, D: DelayNs> From<AsyncLcd<I, D>> for (I, D) {
+    fn from(value: AsyncLcd<I, D>) -> Self {
+        (value.interface, value.delay)
+    }
+}
+
+impl<I: AsyncInterface, D: DelayNs> AsyncLcd<I, D> {
+    #[inline]
+    pub fn new(interface: I, delay: D) -> Self {
+        Self {
+            interface,
+            delay,
+            charset: Charset::default(),
+        }
+    }
+
+    /// Sets how [`AsyncLcd::print`] translates text for the character ROM.
+    #[inline]
+    pub fn with_charset(self, charset: Charset) -> Self {
+        Self { charset, ..self }
+    }
+
+    /// Runs the same power-on sequence as [`LcdInit`](crate::init::LcdInit).
+    pub async fn init(
+        &mut self,
+        function_set: FunctionSet,
+        entry_mode: EntryModeSet,
+    ) -> Result<(), I::Error> {
+        let Self {
+            interface, delay, ..
+        } = self;
+        delay.delay_us(POWER_ON_US).await;
+        interface.write_nibble(delay, 0x3).await?;
+        delay.delay_us(FIRST_WAKE_US).await;
+        interface.write_nibble(delay, 0x3).await?;
+        delay.delay_us(WAKE_US).await;
+        interface.write_nibble(delay, 0x3).await?;
+        delay.delay_us(WAKE_US).await;
+        if let DataLength::Four = I::DATA_LENGTH {
+            interface.write_nibble(delay, 0x2).await?;
+            delay.delay_us(WAKE_US).await;
+        }
+        let function_set = FunctionSet {
+            data_length: I::DATA_LENGTH,
+            ..function_set
+        };
+        interface
+            .write(delay, function_set.compile().into())
+            .await?;
+        interface
+            .write(delay, DisplayControl::default().compile().into())
+            .await?;
+        interface.write(delay, Clear::compile().into()).await?;
+        interface.write(delay, entry_mode.compile().into()).await
+    }
+
+    /// Directs the following DDRAM transfers to one of several controllers.
+    #[inline]
+    pub fn select(&mut self, controller: u8) {
+        self.interface.select(controller)
+    }
+
+    pub async fn write(&mut self, deliverable: Deliverable) -> Result<(), I::Error> {
+        self.interface.write(&mut self.delay, deliverable).await
+    }
+
+    pub async fn print(&mut self, s: &str) -> Result<(), I::Error> {
+        let charset = self.charset;
+        for code in s.chars().flat_map(|c| charset.codes(c)) {
+            self.write(Deliverable::Data(code)).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: AsyncReadInterface, D: DelayNs> AsyncLcd<I, D> {
+    pub async fn state(&mut self) -> Result<State, I::Error> {
+        self.interface.state(&mut self.delay).await
+    }
+}
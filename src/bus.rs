@@ -0,0 +1,162 @@
+use hal::digital::{ErrorType, InputPin, OutputPin, PinState};
+
+use crate::instr::DataLength;
+use crate::{hal, DataBus};
+
+/// A 4-line [`DataBus`] over individual DB4–DB7 pins that can be read back while released,
+/// such as open-drain outputs with pull-ups. [`DataBus::set_input_mode`] releases the lines by
+/// setting them high. Pins that switch direction go in a [`FlexBus`] instead.
+pub struct GpioBus<D4, D5, D6, D7>
+where
+    D4: OutputPin + InputPin,
+    D5: OutputPin + InputPin,
+    D6: OutputPin + InputPin,
+    D7: OutputPin + InputPin,
+{
+    db4: D4,
+    db5: D5,
+    db6: D6,
+    db7: D7,
+}
+
+pub enum GpioBusError<D4: ErrorType, D5: ErrorType, D6: ErrorType, D7: ErrorType> {
+    Db4Error(D4::Error),
+    Db5Error(D5::Error),
+    Db6Error(D6::Error),
+    Db7Error(D7::Error),
+}
+
+impl<D4, D5, D6, D7> GpioBus<D4, D5, D6, D7>
+where
+    D4: OutputPin + InputPin,
+    D5: OutputPin + InputPin,
+    D6: OutputPin + InputPin,
+    D7: OutputPin + InputPin,
+{
+    #[inline]
+    pub fn new(db4: D4, db5: D5, db6: D6, db7: D7) -> Self {
+        Self { db4, db5, db6, db7 }
+    }
+}
+
+impl<D4, D5, D6, D7> DataBus for GpioBus<D4, D5, D6, D7>
+where
+    D4: OutputPin + InputPin,
+    D5: OutputPin + InputPin,
+    D6: OutputPin + InputPin,
+    D7: OutputPin + InputPin,
+{
+    type Error = GpioBusError<D4, D5, D6, D7>;
+
+    fn write_pins_now(
+        &mut self,
+        mut states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        let mut next = || states.next().unwrap_or(PinState::Low);
+        self.db4
+            .set_state(next())
+            .map_err(|e| GpioBusError::Db4Error(e))?;
+        self.db5
+            .set_state(next())
+            .map_err(|e| GpioBusError::Db5Error(e))?;
+        self.db6
+            .set_state(next())
+            .map_err(|e| GpioBusError::Db6Error(e))?;
+        self.db7
+            .set_state(next())
+            .map_err(|e| GpioBusError::Db7Error(e))?;
+        Ok(())
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        Ok([
+            PinState::from(self.db4.is_high().map_err(|e| GpioBusError::Db4Error(e))?),
+            PinState::from(self.db5.is_high().map_err(|e| GpioBusError::Db5Error(e))?),
+            PinState::from(self.db6.is_high().map_err(|e| GpioBusError::Db6Error(e))?),
+            PinState::from(self.db7.is_high().map_err(|e| GpioBusError::Db7Error(e))?),
+        ]
+        .into_iter())
+    }
+
+    fn set_input_mode(&mut self) -> Result<(), Self::Error> {
+        self.write_pins_now([PinState::High; 4].into_iter())
+    }
+}
+
+impl<D4, D5, D6, D7> From<GpioBus<D4, D5, D6, D7>> for (D4, D5, D6, D7)
+where
+    D4: OutputPin + InputPin,
+    D5: OutputPin + InputPin,
+    D6: OutputPin + InputPin,
+    D7: OutputPin + InputPin,
+{
+    #[inline]
+    fn from(value: GpioBus<D4, D5, D6, D7>) -> Self {
+        (value.db4, value.db5, value.db6, value.db7)
+    }
+}
+
+/// A pin whose direction is switched in place, as offered by the flexible pins of many
+/// embedded-hal 1.0 HALs, which have no common trait for it.
+pub trait FlexPin: OutputPin + InputPin {
+    fn set_as_input(&mut self) -> Result<(), Self::Error>;
+
+    /// Makes the pin an output again, driving the level it was last set to.
+    fn set_as_output(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A [`DataBus`] over 4 (DB4–DB7) or 8 (DB0–DB7) [`FlexPin`]s, lowest data line first, which
+/// become inputs while the controller drives the lines.
+pub struct FlexBus<P: FlexPin, const N: usize> {
+    pins: [P; N],
+}
+
+impl<P: FlexPin, const N: usize> FlexBus<P, N> {
+    #[inline]
+    pub fn new(pins: [P; N]) -> Self {
+        Self { pins }
+    }
+}
+
+impl<P: FlexPin, const N: usize> DataBus for FlexBus<P, N> {
+    type Error = P::Error;
+
+    const DATA_LENGTH: DataLength = match N {
+        4 => DataLength::Four,
+        8 => DataLength::Eight,
+        _ => panic!("a data bus has either 4 or 8 lines"),
+    };
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        self.pins
+            .iter_mut()
+            .zip(states)
+            .try_for_each(|(pin, state)| pin.set_state(state))
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        let mut states = [PinState::Low; N];
+        for (state, pin) in states.iter_mut().zip(self.pins.iter_mut()) {
+            *state = PinState::from(pin.is_high()?);
+        }
+        Ok(states.into_iter())
+    }
+
+    fn set_input_mode(&mut self) -> Result<(), Self::Error> {
+        self.pins.iter_mut().try_for_each(FlexPin::set_as_input)
+    }
+
+    fn set_output_mode(&mut self) -> Result<(), Self::Error> {
+        self.pins.iter_mut().try_for_each(FlexPin::set_as_output)
+    }
+}
+
+impl<P: FlexPin, const N: usize> From<FlexBus<P, N>> for [P; N] {
+    #[inline]
+    fn from(value: FlexBus<P, N>) -> Self {
+        value.pins
+    }
+}
@@ -0,0 +1,248 @@
+use crate::glyph::Glyph;
+use crate::kana;
+
+/// Character ROM of the controller, given by the suffix of its part number such as
+/// HD44780UA00.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Rom {
+    /// Japanese, with half-width katakana. `\` and `~` show as `¥` and `→`.
+    #[default]
+    A00,
+    /// European, with Latin-1 letters and some Cyrillic and Greek.
+    A02,
+}
+
+impl Rom {
+    /// The codes showing `c`, if the ROM has them. On [`Rom::A00`], full-width kana are
+    /// translated by [`kana::encode`].
+    pub fn encode(self, c: char) -> Option<Codes> {
+        match self {
+            Self::A00 => a00(c).map(Codes::one).or_else(|| kana::encode(c)),
+            Self::A02 => a02(c).map(Codes::one),
+        }
+    }
+
+    /// Glyphs that can be uploaded to CGRAM for characters missing from the ROM.
+    fn glyphs(self) -> &'static [(char, Glyph)] {
+        match self {
+            Self::A00 => &A00_GLYPHS,
+            Self::A02 => &[],
+        }
+    }
+}
+
+fn a00(c: char) -> Option<u8> {
+    let code = match c {
+        ' '..='[' | ']'..='}' => c as u8,
+        '¥' => 0x5C,
+        '→' => 0x7E,
+        '←' => 0x7F,
+        '\u{FF61}'..='\u{FF9F}' => (c as u32 - 0xFF61 + 0xA1) as u8,
+        '°' => 0xDF,
+        'α' => 0xE0,
+        'ä' => 0xE1,
+        'β' | 'ß' => 0xE2,
+        'ε' => 0xE3,
+        'μ' | 'µ' => 0xE4,
+        'σ' => 0xE5,
+        'ρ' => 0xE6,
+        '√' => 0xE8,
+        '¢' => 0xEC,
+        'ñ' => 0xEE,
+        'ö' => 0xEF,
+        'θ' => 0xF2,
+        '∞' => 0xF3,
+        'Ω' => 0xF4,
+        'ü' => 0xF5,
+        'Σ' => 0xF6,
+        'π' => 0xF7,
+        '千' => 0xFA,
+        '万' => 0xFB,
+        '円' => 0xFC,
+        '÷' => 0xFD,
+        '█' => 0xFF,
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn a02(c: char) -> Option<u8> {
+    let code = match c {
+        ' '..='~' => c as u8,
+        'Б' => 0x80,
+        'Д' => 0x81,
+        'Ж' => 0x82,
+        'З' => 0x83,
+        'И' => 0x84,
+        'Й' => 0x85,
+        'Л' => 0x86,
+        'П' => 0x87,
+        'У' => 0x88,
+        'Ц' => 0x89,
+        'Ч' => 0x8A,
+        'Ш' => 0x8B,
+        'Щ' => 0x8C,
+        'Ъ' => 0x8D,
+        'Ы' => 0x8E,
+        'Э' => 0x8F,
+        'α' => 0x90,
+        '♪' => 0x91,
+        'Γ' => 0x92,
+        'π' => 0x93,
+        'Σ' => 0x94,
+        'σ' => 0x95,
+        'τ' => 0x97,
+        'Θ' => 0x99,
+        'Ω' => 0x9A,
+        'δ' => 0x9B,
+        '∞' => 0x9C,
+        '♥' => 0x9D,
+        'ε' => 0x9E,
+        '∩' => 0x9F,
+        '¡'..='§' | '©'..='«' | '®' | 'À'..='ÿ' => c as u8,
+        _ => return None,
+    };
+    Some(code)
+}
+
+const A00_GLYPHS: [(char, Glyph); 2] = [
+    (
+        '\\',
+        Glyph::Dots5x8([
+            0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000, 0b00000,
+        ]),
+    ),
+    (
+        '~',
+        Glyph::Dots5x8([
+            0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000, 0b00000,
+        ]),
+    ),
+];
+
+/// One or two codes of the character ROM, the second being a separate sound mark such as
+/// ﾞ after a half-width katakana.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Codes {
+    codes: [u8; 2],
+    start: u8,
+    end: u8,
+}
+
+impl Codes {
+    #[inline]
+    pub(crate) const fn one(code: u8) -> Self {
+        Self {
+            codes: [code, 0],
+            start: 0,
+            end: 1,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn two(code: u8, mark: u8) -> Self {
+        Self {
+            codes: [code, mark],
+            start: 0,
+            end: 2,
+        }
+    }
+}
+
+impl Iterator for Codes {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(self.codes[self.start as usize - 1])
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Codes {}
+
+/// What a character turns into on the display.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoded {
+    /// Codes of the character ROM, or the fallback.
+    Codes(Codes),
+    /// A glyph to upload to CGRAM, as the ROM lacks the character.
+    Glyph(&'static Glyph),
+}
+
+/// Translation of Unicode text into codes of a character [`Rom`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Charset {
+    rom: Rom,
+    fallback: u8,
+    synthesize: bool,
+}
+
+impl Default for Charset {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Rom::default())
+    }
+}
+
+impl Charset {
+    /// Shows characters missing from `rom` as `?`, without synthesizing glyphs.
+    #[inline]
+    pub const fn new(rom: Rom) -> Self {
+        Self {
+            rom,
+            fallback: b'?',
+            synthesize: false,
+        }
+    }
+
+    /// Sets the code shown for characters that cannot be mapped.
+    #[inline]
+    pub const fn with_fallback(self, fallback: u8) -> Self {
+        Self { fallback, ..self }
+    }
+
+    /// Sets whether some characters missing from the ROM, such as `\` and `~` on
+    /// [`Rom::A00`], are drawn with CGRAM glyphs where the output supports it.
+    #[inline]
+    pub const fn with_synthesis(self, synthesize: bool) -> Self {
+        Self { synthesize, ..self }
+    }
+
+    #[inline]
+    pub const fn rom(&self) -> Rom {
+        self.rom
+    }
+
+    #[inline]
+    pub const fn fallback(&self) -> u8 {
+        self.fallback
+    }
+
+    pub fn encode(&self, c: char) -> Encoded {
+        if let Some(codes) = self.rom.encode(c) {
+            return Encoded::Codes(codes);
+        }
+        if self.synthesize {
+            if let Some((_, glyph)) = self.rom.glyphs().iter().find(|(g, _)| *g == c) {
+                return Encoded::Glyph(glyph);
+            }
+        }
+        Encoded::Codes(Codes::one(self.fallback))
+    }
+
+    /// The ROM codes of `c`, or the fallback, for outputs that cannot synthesize glyphs.
+    pub fn codes(&self, c: char) -> Codes {
+        self.rom.encode(c).unwrap_or(Codes::one(self.fallback))
+    }
+}
@@ -0,0 +1,325 @@
+use core::fmt::Debug;
+use core::mem;
+
+use hal::delay::DelayNs;
+use hal::digital::{self, PinState};
+use hal::i2c::{self, I2c};
+use hal::spi::{self, SpiDevice};
+use hal02::blocking::delay::DelayUs;
+use hal02::blocking::i2c::{Read, Write, WriteRead};
+use hal02::blocking::spi::{Transfer, Write as SpiWrite};
+use hal02::digital::v2::{InputPin, IoPin, OutputPin, PinState as PinState02};
+
+use crate::instr::DataLength;
+use crate::{hal, hal02, DataBus};
+
+/// Adapts an embedded-hal 0.2 output pin (optionally also an input pin), delay or blocking I2C
+/// bus to the embedded-hal 1.0 traits used throughout this crate.
+pub struct Compat<T>(pub T);
+
+#[derive(Debug)]
+pub struct CompatError<E>(pub E);
+
+impl<E: Debug> digital::Error for CompatError<E> {
+    #[inline]
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+impl<E: Debug> i2c::Error for CompatError<E> {
+    #[inline]
+    fn kind(&self) -> i2c::ErrorKind {
+        i2c::ErrorKind::Other
+    }
+}
+
+impl<T: OutputPin> digital::ErrorType for Compat<T>
+where
+    T::Error: Debug,
+{
+    type Error = CompatError<T::Error>;
+}
+
+impl<T: OutputPin> digital::OutputPin for Compat<T>
+where
+    T::Error: Debug,
+{
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low().map_err(CompatError)
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high().map_err(CompatError)
+    }
+}
+
+impl<T: OutputPin + InputPin<Error = <T as OutputPin>::Error>> digital::InputPin for Compat<T>
+where
+    <T as OutputPin>::Error: Debug,
+{
+    #[inline]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_high().map_err(CompatError)
+    }
+
+    #[inline]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low().map_err(CompatError)
+    }
+}
+
+impl<T: DelayUs<u32>> DelayNs for Compat<T> {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns.div_ceil(1_000));
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+}
+
+impl<T, E: Debug> i2c::ErrorType for Compat<T>
+where
+    T: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>,
+{
+    type Error = CompatError<E>;
+}
+
+/// Operations of a transaction are issued one by one, each with its own start and stop
+/// condition, apart from [`I2c::write_read`] which maps onto [`WriteRead`].
+impl<T, E: Debug> I2c for Compat<T>
+where
+    T: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        operations.iter_mut().try_for_each(|operation| {
+            match operation {
+                i2c::Operation::Read(buffer) => self.0.read(address, buffer),
+                i2c::Operation::Write(bytes) => self.0.write(address, bytes),
+            }
+            .map_err(CompatError)
+        })
+    }
+
+    #[inline]
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.0.write_read(address, write, read).map_err(CompatError)
+    }
+}
+
+/// Adapts an embedded-hal 0.2 SPI bus and its chip select pin to [`SpiDevice`], holding chip
+/// select low for the duration of each transaction.
+pub struct CompatSpiDevice<SPI, CS> {
+    spi: SPI,
+    chip_select: CS,
+}
+
+#[derive(Debug)]
+pub enum CompatSpiError<E, CSE> {
+    SpiError(E),
+    ChipSelectError(CSE),
+    /// [`spi::Operation::DelayNs`] cannot be expressed with embedded-hal 0.2.
+    UnsupportedOperation,
+}
+
+impl<E: Debug, CSE: Debug> spi::Error for CompatSpiError<E, CSE> {
+    #[inline]
+    fn kind(&self) -> spi::ErrorKind {
+        match self {
+            Self::ChipSelectError(_) => spi::ErrorKind::ChipSelectFault,
+            _ => spi::ErrorKind::Other,
+        }
+    }
+}
+
+impl<SPI, CS: OutputPin> CompatSpiDevice<SPI, CS> {
+    #[inline]
+    pub fn new(spi: SPI, chip_select: CS) -> Self {
+        Self { spi, chip_select }
+    }
+}
+
+impl<SPI, CS> From<CompatSpiDevice<SPI, CS>> for (SPI, CS) {
+    #[inline]
+    fn from(value: CompatSpiDevice<SPI, CS>) -> Self {
+        (value.spi, value.chip_select)
+    }
+}
+
+impl<SPI, CS, E: Debug> spi::ErrorType for CompatSpiDevice<SPI, CS>
+where
+    SPI: SpiWrite<u8, Error = E> + Transfer<u8, Error = E>,
+    CS: OutputPin,
+    CS::Error: Debug,
+{
+    type Error = CompatSpiError<E, CS::Error>;
+}
+
+impl<SPI, CS, E: Debug> SpiDevice for CompatSpiDevice<SPI, CS>
+where
+    SPI: SpiWrite<u8, Error = E> + Transfer<u8, Error = E>,
+    CS: OutputPin,
+    CS::Error: Debug,
+{
+    fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.chip_select
+            .set_low()
+            .map_err(CompatSpiError::ChipSelectError)?;
+        let result = operations
+            .iter_mut()
+            .try_for_each(|operation| match operation {
+                spi::Operation::Read(words) => {
+                    words.fill(0);
+                    self.spi
+                        .transfer(words)
+                        .map(|_| ())
+                        .map_err(CompatSpiError::SpiError)
+                }
+                spi::Operation::Write(words) => {
+                    self.spi.write(words).map_err(CompatSpiError::SpiError)
+                }
+                spi::Operation::Transfer(read, write) => {
+                    let len = read.len().min(write.len());
+                    read[..len].copy_from_slice(&write[..len]);
+                    read[len..].fill(0);
+                    self.spi.transfer(read).map_err(CompatSpiError::SpiError)?;
+                    self.spi
+                        .write(&write[len..])
+                        .map_err(CompatSpiError::SpiError)
+                }
+                spi::Operation::TransferInPlace(words) => self
+                    .spi
+                    .transfer(words)
+                    .map(|_| ())
+                    .map_err(CompatSpiError::SpiError),
+                spi::Operation::DelayNs(_) => Err(CompatSpiError::UnsupportedOperation),
+            });
+        self.chip_select
+            .set_high()
+            .map_err(CompatSpiError::ChipSelectError)?;
+        result
+    }
+}
+
+enum Line<I, O> {
+    Input(I),
+    Output(O),
+    Lost,
+}
+
+/// A [`DataBus`] over 4 (DB4–DB7) or 8 (DB0–DB7) pins that change direction by value through
+/// [`IoPin`], lowest data line first.
+pub struct IoPinBus<I, O, const N: usize> {
+    lines: [Line<I, O>; N],
+}
+
+#[derive(Debug)]
+pub enum IoPinBusError<E> {
+    PinError(E),
+    /// A pin was consumed by an earlier direction switch that failed.
+    LostPin,
+}
+
+impl<I, O, E, const N: usize> IoPinBus<I, O, N>
+where
+    I: InputPin<Error = E> + IoPin<I, O, Error = E>,
+    O: OutputPin<Error = E> + IoPin<I, O, Error = E>,
+{
+    #[inline]
+    pub fn new(pins: [O; N]) -> Self {
+        Self {
+            lines: pins.map(Line::Output),
+        }
+    }
+}
+
+impl<I, O, E, const N: usize> DataBus for IoPinBus<I, O, N>
+where
+    I: InputPin<Error = E> + IoPin<I, O, Error = E>,
+    O: OutputPin<Error = E> + IoPin<I, O, Error = E>,
+{
+    type Error = IoPinBusError<E>;
+
+    const DATA_LENGTH: DataLength = match N {
+        4 => DataLength::Four,
+        8 => DataLength::Eight,
+        _ => panic!("a data bus has either 4 or 8 lines"),
+    };
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        for (line, state) in self.lines.iter_mut().zip(states) {
+            *line = match mem::replace(line, Line::Lost) {
+                Line::Output(mut pin) => {
+                    pin.set_state(PinState02::from(bool::from(state)))
+                        .map_err(IoPinBusError::PinError)?;
+                    Line::Output(pin)
+                }
+                Line::Input(pin) => Line::Output(
+                    pin.into_output_pin(PinState02::from(bool::from(state)))
+                        .map_err(IoPinBusError::PinError)?,
+                ),
+                Line::Lost => return Err(IoPinBusError::LostPin),
+            };
+        }
+        Ok(())
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        self.set_input_mode()?;
+        let mut states = [PinState::Low; N];
+        for (state, line) in states.iter_mut().zip(self.lines.iter()) {
+            *state = match line {
+                Line::Input(pin) => PinState::from(pin.is_high().map_err(IoPinBusError::PinError)?),
+                _ => return Err(IoPinBusError::LostPin),
+            };
+        }
+        Ok(states.into_iter())
+    }
+
+    fn set_input_mode(&mut self) -> Result<(), Self::Error> {
+        for line in self.lines.iter_mut() {
+            *line = match mem::replace(line, Line::Lost) {
+                Line::Input(pin) => Line::Input(pin),
+                Line::Output(pin) => {
+                    Line::Input(pin.into_input_pin().map_err(IoPinBusError::PinError)?)
+                }
+                Line::Lost => return Err(IoPinBusError::LostPin),
+            };
+        }
+        Ok(())
+    }
+
+    fn set_output_mode(&mut self) -> Result<(), Self::Error> {
+        for line in self.lines.iter_mut() {
+            *line = match mem::replace(line, Line::Lost) {
+                Line::Output(pin) => Line::Output(pin),
+                Line::Input(pin) => Line::Output(
+                    pin.into_output_pin(PinState02::Low)
+                        .map_err(IoPinBusError::PinError)?,
+                ),
+                Line::Lost => return Err(IoPinBusError::LostPin),
+            };
+        }
+        Ok(())
+    }
+}
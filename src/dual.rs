@@ -0,0 +1,74 @@
+use hal::digital::{ErrorType, OutputPin};
+
+use crate::{hal, EnablePin};
+
+/// The E1 and E2 lines of a display made of two controllers sharing RS, RW and the data
+/// lines, such as 40x4 modules where the first controller drives rows 0–1 and the second
+/// rows 2–3.
+///
+/// Used as the E line of [`LcdPins`](crate::LcdPins), which then initializes both
+/// controllers, checks their busy flags one after the other and sends DDRAM transfers to the
+/// controller chosen through [`Interface::select`](crate::Interface::select).
+pub struct DualEnable<E1: OutputPin, E2: OutputPin> {
+    first: E1,
+    second: E2,
+    selected: Option<u8>,
+}
+
+pub enum DualEnableError<E1: ErrorType, E2: ErrorType> {
+    FirstError(E1::Error),
+    SecondError(E2::Error),
+}
+
+impl<E1: OutputPin, E2: OutputPin> DualEnable<E1, E2> {
+    #[inline]
+    pub fn new(first: E1, second: E2) -> Self {
+        Self {
+            first,
+            second,
+            selected: None,
+        }
+    }
+
+    fn set_state(&mut self, high: bool) -> Result<(), DualEnableError<E1, E2>> {
+        if self.selected != Some(1) {
+            self.first
+                .set_state(high.into())
+                .map_err(|e| DualEnableError::FirstError(e))?;
+        }
+        if self.selected != Some(0) {
+            self.second
+                .set_state(high.into())
+                .map_err(|e| DualEnableError::SecondError(e))?;
+        }
+        Ok(())
+    }
+}
+
+impl<E1: OutputPin, E2: OutputPin> EnablePin for DualEnable<E1, E2> {
+    type Error = DualEnableError<E1, E2>;
+
+    const CONTROLLERS: u8 = 2;
+
+    #[inline]
+    fn select(&mut self, controller: Option<u8>) {
+        self.selected = controller;
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_state(true)
+    }
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_state(false)
+    }
+}
+
+impl<E1: OutputPin, E2: OutputPin> From<DualEnable<E1, E2>> for (E1, E2) {
+    #[inline]
+    fn from(value: DualEnable<E1, E2>) -> Self {
+        (value.first, value.second)
+    }
+}
@@ -0,0 +1,700 @@
+use core::convert::Infallible;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::string::String;
+use std::vec::Vec;
+
+use hal::delay::DelayNs;
+use hal::digital::{ErrorType, OutputPin, PinState};
+
+use crate::geometry::Geometry;
+use crate::instr::{DataLength, DisplayControl};
+use crate::{hal, DataBus, LcdPins};
+
+/// Time the internal reset keeps the controller busy after power-on.
+const RESET_US: u64 = 10_000;
+/// Execution time of Clear and Return Home.
+const HOME_US: u64 = 1_520;
+/// Execution time of every other instruction and of data writes.
+const INSTR_US: u64 = 37;
+/// Execution time of the first Function Set after power-on, and of the second one.
+const WAKE_US: [u64; 2] = [4_100, 100];
+
+const ENABLE_PULSE_WIDTH_NS: u64 = 450;
+const ENABLE_CYCLE_NS: u64 = 1_000;
+const ADDRESS_SETUP_NS: u64 = 60;
+const ADDRESS_HOLD_NS: u64 = 20;
+const DATA_SETUP_NS: u64 = 195;
+const DATA_HOLD_NS: u64 = 10;
+const DATA_DELAY_NS: u64 = 360;
+const READ_HOLD_NS: u64 = 5;
+
+/// A software HD44780 for testing drivers on the host, wired through the pins it hands out.
+///
+/// The emulator decodes E edges into 4-bit or 8-bit transfers, keeps DDRAM, CGRAM, the
+/// address counter, entry mode and display shift, and starts in 8-bit mode after an internal
+/// reset like the real controller. Time only moves on through the [`EmulatorDelay`], and
+/// writes arriving while the busy flag is set are dropped and counted, as are the wake-up
+/// Function Sets sent without waiting the times of the initialization by instruction.
+///
+/// Every E pulse is also checked against the bus timing of the datasheet, see [`Timing`],
+/// and the [`violations`](Emulator::violations) are kept with the offending transaction.
+///
+/// The visible screen is rendered along a [`Geometry`], showing characters outside printable
+/// ASCII as U+FFFD.
+#[derive(Clone)]
+pub struct Emulator {
+    controller: Rc<RefCell<Controller>>,
+}
+
+/// RS, RW or E of an [`Emulator`].
+pub struct EmulatorPin {
+    controller: Rc<RefCell<Controller>>,
+    line: Line,
+}
+
+/// DB4–DB7, or DB0–DB7 for `LINES` = 8, of an [`Emulator`].
+pub struct EmulatorBus<const LINES: usize> {
+    controller: Rc<RefCell<Controller>>,
+}
+
+/// A delay moving the clock of an [`Emulator`] on instead of sleeping.
+pub struct EmulatorDelay {
+    controller: Rc<RefCell<Controller>>,
+}
+
+/// A timing requirement of the bus, with the datasheet values for a 2.7 V to 4.5 V supply.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Timing {
+    /// E stays high for at least 450 ns.
+    EnablePulseWidth,
+    /// E rises at most once per 1 µs.
+    EnableCycle,
+    /// RS and RW settle at least 60 ns before E rises.
+    AddressSetup,
+    /// RS and RW hold for at least 20 ns after E falls.
+    AddressHold,
+    /// Written data settles at least 195 ns before E falls.
+    DataSetup,
+    /// Written data holds for at least 10 ns after E falls.
+    DataHold,
+    /// Read data is sampled no sooner than 360 ns after E rises.
+    DataDelay,
+    /// Read data is sampled no later than 5 ns after E falls.
+    ReadHold,
+    /// Nothing is written while the busy flag is set.
+    Busy,
+}
+
+/// One E pulse, with the lines as they were when it rose, or when it fell for the data of a
+/// write.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Transaction {
+    /// Time E rose.
+    pub at_ns: u64,
+    pub rs: bool,
+    pub rw: bool,
+    /// DB0–DB7 as driven by the host for a write, or by the controller for a read. Only
+    /// DB4–DB7 are wired on a 4-bit bus.
+    pub data: u8,
+}
+
+/// A [`Timing`] requirement missed by a [`Transaction`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Violation {
+    pub timing: Timing,
+    /// Time the violation was seen.
+    pub at_ns: u64,
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Copy)]
+enum Line {
+    RegisterSelection,
+    ReadWrite,
+    Enable,
+}
+
+struct Controller {
+    geometry: Geometry,
+    now_ns: u64,
+    busy_until_ns: u64,
+    rs: bool,
+    rw: bool,
+    enable: bool,
+    /// DB0–DB7 as driven by the host.
+    input: u8,
+    /// DB0–DB7 as driven by the controller during a read.
+    output: u8,
+    eight_bit: bool,
+    two_lines: bool,
+    /// High nibble of a 4-bit write waiting for the low one.
+    pending: Option<u8>,
+    /// Byte being read, which takes two pulses in 4-bit mode.
+    reading: Option<u8>,
+    /// Whether the next read pulse carries the low nibble.
+    low_nibble: bool,
+    ddram: [u8; 128],
+    cgram: [u8; 64],
+    ac: u8,
+    cgram_selected: bool,
+    increment: bool,
+    shift_on_entry: bool,
+    display: bool,
+    cursor: bool,
+    blink: bool,
+    shift: u8,
+    wakes: usize,
+    dropped: usize,
+    /// Last time RS or RW changed.
+    address_changed_ns: u64,
+    /// Last time the host changed the data lines.
+    input_changed_ns: u64,
+    rose_ns: Option<u64>,
+    fell_ns: Option<u64>,
+    /// The last E pulse.
+    transaction: Transaction,
+    violations: Vec<Violation>,
+}
+
+impl Default for Emulator {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Geometry::default())
+    }
+}
+
+impl Emulator {
+    /// A controller just powered on, whose screen is laid out along `geometry`.
+    pub fn new(geometry: Geometry) -> Self {
+        let controller = Controller {
+            geometry,
+            now_ns: 0,
+            busy_until_ns: RESET_US * 1_000,
+            rs: false,
+            rw: false,
+            enable: false,
+            input: 0,
+            output: 0,
+            eight_bit: true,
+            two_lines: false,
+            pending: None,
+            reading: None,
+            low_nibble: false,
+            ddram: [b' '; 128],
+            cgram: [0; 64],
+            ac: 0,
+            cgram_selected: false,
+            increment: true,
+            shift_on_entry: false,
+            display: false,
+            cursor: false,
+            blink: false,
+            shift: 0,
+            wakes: 0,
+            dropped: 0,
+            address_changed_ns: 0,
+            input_changed_ns: 0,
+            rose_ns: None,
+            fell_ns: None,
+            transaction: Transaction {
+                at_ns: 0,
+                rs: false,
+                rw: false,
+                data: 0,
+            },
+            violations: Vec::new(),
+        };
+        Self {
+            controller: Rc::new(RefCell::new(controller)),
+        }
+    }
+
+    fn pin(&self, line: Line) -> EmulatorPin {
+        EmulatorPin {
+            controller: self.controller.clone(),
+            line,
+        }
+    }
+
+    #[inline]
+    pub fn register_selection(&self) -> EmulatorPin {
+        self.pin(Line::RegisterSelection)
+    }
+
+    #[inline]
+    pub fn read_write(&self) -> EmulatorPin {
+        self.pin(Line::ReadWrite)
+    }
+
+    #[inline]
+    pub fn enable(&self) -> EmulatorPin {
+        self.pin(Line::Enable)
+    }
+
+    #[inline]
+    pub fn four_bit_bus(&self) -> EmulatorBus<4> {
+        EmulatorBus {
+            controller: self.controller.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn eight_bit_bus(&self) -> EmulatorBus<8> {
+        EmulatorBus {
+            controller: self.controller.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn delay(&self) -> EmulatorDelay {
+        EmulatorDelay {
+            controller: self.controller.clone(),
+        }
+    }
+
+    /// Every line of a 4-bit module, RW included.
+    #[inline]
+    pub fn pins(&self) -> LcdPins<EmulatorPin, EmulatorPin, EmulatorPin, EmulatorBus<4>> {
+        LcdPins::new(
+            self.register_selection(),
+            self.read_write(),
+            self.enable(),
+            self.four_bit_bus(),
+        )
+    }
+
+    /// The text of every row, blank while the display is off.
+    pub fn screen(&self) -> Vec<String> {
+        let controller = self.controller.borrow();
+        let geometry = controller.geometry;
+        (0..geometry.rows())
+            .map(|row| {
+                (0..geometry.cols())
+                    .map(|col| match controller.visible(row, col) {
+                        _ if !controller.display => ' ',
+                        code @ 0x20..=0x7E => code as char,
+                        _ => char::REPLACEMENT_CHARACTER,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The character code shown at a position, whether the display is on or not.
+    #[inline]
+    pub fn code_at(&self, row: u8, col: u8) -> u8 {
+        self.controller.borrow().visible(row, col)
+    }
+
+    #[inline]
+    pub fn ddram(&self) -> [u8; 128] {
+        self.controller.borrow().ddram
+    }
+
+    #[inline]
+    pub fn cgram(&self) -> [u8; 64] {
+        self.controller.borrow().cgram
+    }
+
+    #[inline]
+    pub fn address_counter(&self) -> u8 {
+        self.controller.borrow().ac
+    }
+
+    /// The last Display On/Off Control executed.
+    pub fn display_control(&self) -> DisplayControl {
+        let controller = self.controller.borrow();
+        DisplayControl {
+            display: controller.display,
+            cursor: controller.cursor,
+            blink: controller.blink,
+        }
+    }
+
+    /// How many positions the display has been shifted to the left.
+    #[inline]
+    pub fn display_shift(&self) -> u8 {
+        self.controller.borrow().shift
+    }
+
+    #[inline]
+    pub fn is_four_bit(&self) -> bool {
+        !self.controller.borrow().eight_bit
+    }
+
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.controller.borrow().busy()
+    }
+
+    /// Number of writes ignored because the controller was still busy.
+    #[inline]
+    pub fn dropped_writes(&self) -> usize {
+        self.controller.borrow().dropped
+    }
+
+    /// Every timing violation seen so far, oldest first.
+    #[inline]
+    pub fn violations(&self) -> Vec<Violation> {
+        self.controller.borrow().violations.clone()
+    }
+
+    #[inline]
+    pub fn clear_violations(&self) {
+        self.controller.borrow_mut().violations.clear();
+    }
+
+    #[inline]
+    pub fn now_us(&self) -> u64 {
+        self.controller.borrow().now_ns / 1_000
+    }
+}
+
+impl Controller {
+    fn busy(&self) -> bool {
+        self.now_ns < self.busy_until_ns
+    }
+
+    fn violation(&mut self, timing: Timing) {
+        self.violations.push(Violation {
+            timing,
+            at_ns: self.now_ns,
+            transaction: self.transaction,
+        });
+    }
+
+    /// Checks that nothing changes too early after E falls, or while E is high.
+    fn check_hold(&mut self, timing: Timing, hold_ns: u64) {
+        let held = self
+            .fell_ns
+            .is_none_or(|fell_ns| self.now_ns >= fell_ns + hold_ns);
+        if self.enable || !held {
+            self.violation(timing);
+        }
+    }
+
+    fn set_address(&mut self, line: Line, high: bool) {
+        let current = match line {
+            Line::RegisterSelection => &mut self.rs,
+            _ => &mut self.rw,
+        };
+        if core::mem::replace(current, high) != high {
+            self.check_hold(Timing::AddressHold, ADDRESS_HOLD_NS);
+            self.address_changed_ns = self.now_ns;
+        }
+    }
+
+    fn set_input(&mut self, input: u8) {
+        if self.input != input {
+            if !self.transaction.rw {
+                self.check_hold(Timing::DataHold, DATA_HOLD_NS);
+            }
+            self.input = input;
+            self.input_changed_ns = self.now_ns;
+        }
+    }
+
+    fn sample(&mut self) -> u8 {
+        let valid = match (self.enable, self.rose_ns, self.fell_ns) {
+            (true, Some(rose_ns), _) => {
+                if self.now_ns < rose_ns + DATA_DELAY_NS {
+                    self.violation(Timing::DataDelay);
+                }
+                true
+            }
+            (false, _, Some(fell_ns)) => self.now_ns <= fell_ns + READ_HOLD_NS,
+            _ => false,
+        };
+        if !valid || !self.transaction.rw {
+            self.violation(Timing::ReadHold);
+        }
+        self.output
+    }
+
+    fn set_enable(&mut self, high: bool) {
+        match (self.enable, high) {
+            (false, true) => self.rise(),
+            (true, false) => self.fall(),
+            _ => {}
+        }
+        self.enable = high;
+    }
+
+    fn rise(&mut self) {
+        self.transaction = Transaction {
+            at_ns: self.now_ns,
+            rs: self.rs,
+            rw: self.rw,
+            data: self.input,
+        };
+        if self.now_ns < self.address_changed_ns + ADDRESS_SETUP_NS {
+            self.violation(Timing::AddressSetup);
+        }
+        if let Some(rose_ns) = self.rose_ns {
+            if self.now_ns < rose_ns + ENABLE_CYCLE_NS {
+                self.violation(Timing::EnableCycle);
+            }
+        }
+        self.rose_ns = Some(self.now_ns);
+        if self.rw {
+            self.begin_read();
+            self.transaction.data = self.output;
+        }
+    }
+
+    fn fall(&mut self) {
+        if let Some(rose_ns) = self.rose_ns {
+            if self.now_ns < rose_ns + ENABLE_PULSE_WIDTH_NS {
+                self.violation(Timing::EnablePulseWidth);
+            }
+        }
+        self.fell_ns = Some(self.now_ns);
+        if self.rw {
+            return self.end_read();
+        }
+        self.transaction.data = self.input;
+        if self.now_ns < self.input_changed_ns + DATA_SETUP_NS {
+            self.violation(Timing::DataSetup);
+        }
+        self.latch();
+    }
+
+    fn begin_read(&mut self) {
+        let byte = match self.reading {
+            Some(byte) => byte,
+            None => match self.rs {
+                false => (self.busy() as u8) << 7 | self.ac,
+                true => self.read_ram(),
+            },
+        };
+        self.reading = Some(byte);
+        self.output = match self.low_nibble {
+            true => byte << 4,
+            false => byte,
+        };
+    }
+
+    fn end_read(&mut self) {
+        if !self.eight_bit && !self.low_nibble {
+            self.low_nibble = true;
+            return;
+        }
+        self.reading = None;
+        self.low_nibble = false;
+        if self.rs {
+            self.move_ac(self.increment);
+        }
+    }
+
+    fn latch(&mut self) {
+        self.reading = None;
+        self.low_nibble = false;
+        if self.eight_bit {
+            return self.execute(self.input);
+        }
+        let nibble = self.input >> 4;
+        match self.pending.take() {
+            Some(high) => self.execute(high << 4 | nibble),
+            None => self.pending = Some(nibble),
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if self.busy() {
+            self.dropped += 1;
+            return self.violation(Timing::Busy);
+        }
+        let mut time_us = INSTR_US;
+        match (self.rs, byte) {
+            (true, _) => self.write_ram(byte),
+            (false, 0x80..=0xFF) => {
+                self.ac = byte & 0x7F;
+                self.cgram_selected = false;
+            }
+            (false, 0x40..=0x7F) => {
+                self.ac = byte & 0x3F;
+                self.cgram_selected = true;
+            }
+            (false, 0x20..=0x3F) => {
+                self.eight_bit = byte & 0x10 != 0;
+                self.two_lines = byte & 0x08 != 0;
+                self.pending = None;
+                if let Some(&wake_us) = WAKE_US.get(self.wakes) {
+                    time_us = wake_us;
+                }
+                self.wakes += 1;
+            }
+            (false, 0x10..=0x1F) => match byte & 0x08 != 0 {
+                true => self.shift_display(byte & 0x04 == 0),
+                false => self.move_ac(byte & 0x04 != 0),
+            },
+            (false, 0x08..=0x0F) => {
+                self.display = byte & 0x04 != 0;
+                self.cursor = byte & 0x02 != 0;
+                self.blink = byte & 0x01 != 0;
+            }
+            (false, 0x04..=0x07) => {
+                self.increment = byte & 0x02 != 0;
+                self.shift_on_entry = byte & 0x01 != 0;
+            }
+            (false, 0x02..=0x03) => {
+                self.ac = 0;
+                self.cgram_selected = false;
+                self.shift = 0;
+                time_us = HOME_US;
+            }
+            (false, 0x01) => {
+                self.ddram.fill(b' ');
+                self.ac = 0;
+                self.cgram_selected = false;
+                self.increment = true;
+                self.shift = 0;
+                time_us = HOME_US;
+            }
+            (false, _) => {}
+        }
+        self.busy_until_ns = self.now_ns + time_us * 1_000;
+    }
+
+    fn read_ram(&self) -> u8 {
+        match self.cgram_selected {
+            true => self.cgram[self.ac as usize & 0x3F],
+            false => self.ddram[self.ac as usize & 0x7F],
+        }
+    }
+
+    fn write_ram(&mut self, byte: u8) {
+        match self.cgram_selected {
+            true => self.cgram[self.ac as usize & 0x3F] = byte,
+            false => {
+                self.ddram[self.ac as usize & 0x7F] = byte;
+                if self.shift_on_entry {
+                    self.shift_display(self.increment);
+                }
+            }
+        }
+        self.move_ac(self.increment);
+    }
+
+    /// Number of DDRAM positions of a line.
+    fn line_len(&self) -> u8 {
+        match self.two_lines {
+            true => 40,
+            false => 80,
+        }
+    }
+
+    fn move_ac(&mut self, forward: bool) {
+        if self.cgram_selected {
+            self.ac = self.ac.wrapping_add(if forward { 1 } else { 0x3F }) & 0x3F;
+            return;
+        }
+        // In two-line mode the second line follows the first, from 0x27 to 0x40 and back.
+        let index = match self.ac {
+            0x40.. if self.two_lines => self.ac - 0x40 + 40,
+            _ => self.ac,
+        };
+        let index = match forward {
+            true => (index + 1) % 80,
+            false => (index + 79) % 80,
+        };
+        self.ac = match index {
+            40.. if self.two_lines => index - 40 + 0x40,
+            _ => index,
+        };
+    }
+
+    fn shift_display(&mut self, left: bool) {
+        let len = self.line_len();
+        self.shift = match left {
+            true => (self.shift + 1) % len,
+            false => (self.shift + len - 1) % len,
+        };
+    }
+
+    /// The code shown at a position, following the display shift within its line.
+    fn visible(&self, row: u8, col: u8) -> u8 {
+        let Some(addr) = self.geometry.addr(row, col) else {
+            return b' ';
+        };
+        let addr = addr.addr();
+        let len = self.line_len();
+        let (base, pos) = match self.two_lines {
+            true => (addr & 0x40, addr & 0x3F),
+            false => (0, addr),
+        };
+        self.ddram[(base + (pos + self.shift) % len) as usize]
+    }
+}
+
+impl ErrorType for EmulatorPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for EmulatorPin {
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::Low)
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::High)
+    }
+
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        let mut controller = self.controller.borrow_mut();
+        let high = state == PinState::High;
+        match self.line {
+            Line::Enable => controller.set_enable(high),
+            line => controller.set_address(line, high),
+        }
+        Ok(())
+    }
+}
+
+impl<const LINES: usize> DataBus for EmulatorBus<LINES> {
+    type Error = Infallible;
+
+    const DATA_LENGTH: DataLength = match LINES {
+        8 => DataLength::Eight,
+        _ => DataLength::Four,
+    };
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        let first = 8 - LINES;
+        let input = states
+            .take(LINES)
+            .enumerate()
+            .map(|(i, state)| ((state == PinState::High) as u8) << (first + i))
+            .sum();
+        self.controller.borrow_mut().set_input(input);
+        Ok(())
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        let output = self.controller.borrow_mut().sample();
+        let first = 8 - LINES;
+        Ok((first..8).map(move |i| PinState::from(output & (1 << i) != 0)))
+    }
+}
+
+impl DelayNs for EmulatorDelay {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        self.controller.borrow_mut().now_ns += ns as u64;
+    }
+}
+
+/// Completes at once, as the emulated time moves on without waiting.
+#[cfg(feature = "async")]
+impl crate::hal_async::delay::DelayNs for EmulatorDelay {
+    #[inline]
+    async fn delay_ns(&mut self, ns: u32) {
+        DelayNs::delay_ns(self, ns)
+    }
+}
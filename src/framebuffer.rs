@@ -0,0 +1,162 @@
+use crate::charset::Charset;
+use crate::geometry::Geometry;
+use crate::instr::*;
+use crate::utils::DelayMicros;
+use crate::{Interface, Lcd};
+
+/// A `ROWS` x `COLS` screen kept in RAM, of which only the cells that changed since they were
+/// last sent are written to the display by [`FrameBuffer::flush`].
+///
+/// Cells are laid out along the [`Geometry`] of the [`Lcd`], and
+/// cells it does not show are never sent. The display is assumed to be blank at first and to
+/// be left in the default increment entry mode, so that runs of changed cells need a single
+/// Set DDRAM Address.
+pub struct FrameBuffer<const ROWS: usize, const COLS: usize> {
+    cells: [[u8; COLS]; ROWS],
+    /// What the display shows.
+    shadow: [[u8; COLS]; ROWS],
+    /// Selected controller and its address counter, if known.
+    addr: Option<(u8, u8)>,
+    /// Index of the cell a flush resumes from, counted row by row.
+    next: usize,
+    charset: Charset,
+}
+
+impl<const ROWS: usize, const COLS: usize> FrameBuffer<ROWS, COLS> {
+    /// Creates a blank buffer whose [`FrameBuffer::print`] translates text with `charset`,
+    /// which should be the [`Lcd::charset`] of the display it is flushed to.
+    #[inline]
+    pub const fn new(charset: Charset) -> Self {
+        Self {
+            cells: [[b' '; COLS]; ROWS],
+            shadow: [[b' '; COLS]; ROWS],
+            addr: None,
+            next: 0,
+            charset,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+        self.cells.get(row)?.get(col).copied()
+    }
+
+    /// Sets a cell, ignoring positions outside the buffer.
+    #[inline]
+    pub fn set(&mut self, row: usize, col: usize, byte: u8) {
+        if let Some(cell) = self.cells.get_mut(row).and_then(|row| row.get_mut(col)) {
+            *cell = byte;
+        }
+    }
+
+    #[inline]
+    pub fn row_mut(&mut self, row: usize) -> Option<&mut [u8; COLS]> {
+        self.cells.get_mut(row)
+    }
+
+    /// Writes `s` from a position onwards, clipped at the end of the row. Characters that
+    /// need a synthesized glyph show as the fallback of the charset.
+    pub fn print(&mut self, row: usize, col: usize, s: &str) {
+        if let Some(cells) = self.cells.get_mut(row) {
+            cells
+                .iter_mut()
+                .skip(col)
+                .zip(s.chars().flat_map(|c| self.charset.codes(c)))
+                .for_each(|(cell, code)| *cell = code);
+        }
+    }
+
+    /// Fills every cell with spaces.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.cells = [[b' '; COLS]; ROWS];
+    }
+
+    /// Whether some cells have not been sent yet.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.cells != self.shadow
+    }
+
+    /// Forgets what the display shows, so that the next flush sends every cell, such as after
+    /// the display was written to directly.
+    pub fn invalidate(&mut self) {
+        self.shadow
+            .iter_mut()
+            .flatten()
+            .zip(self.cells.iter().flatten())
+            .for_each(|(shadow, &cell)| *shadow = !cell);
+        self.addr = None;
+    }
+
+    /// Sends the changed cells, returning [`nb::Error::WouldBlock`] as soon as the controller
+    /// is busy. Calling it again carries on where it left off.
+    pub fn flush<I: Interface, D: DelayMicros>(
+        &mut self,
+        lcd: &mut Lcd<I, D>,
+    ) -> nb::Result<(), I::Error> {
+        while self.step(lcd)? {}
+        Ok(())
+    }
+
+    /// Performs at most one transfer of a flush, returning [`nb::Error::WouldBlock`] until no
+    /// changed cell is left. Each call takes at most one transfer and a scan of the buffer, so
+    /// it can be driven from a periodic timer interrupt.
+    pub fn poll_flush<I: Interface, D: DelayMicros>(
+        &mut self,
+        lcd: &mut Lcd<I, D>,
+    ) -> nb::Result<(), I::Error> {
+        if self.step(lcd)? {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sends the address or the content of the next changed cell, returning whether any is
+    /// left. Nothing changes when the transfer would block, so that it is retried.
+    fn step<I: Interface, D: DelayMicros>(
+        &mut self,
+        lcd: &mut Lcd<I, D>,
+    ) -> nb::Result<bool, I::Error> {
+        let geometry = lcd.geometry();
+        let Some((row, col, addr)) = self.next_dirty(geometry) else {
+            return Ok(false);
+        };
+        let controller = geometry.controller(row as u8);
+        if self.addr != Some((controller, addr.addr())) {
+            lcd.interface.select(controller);
+            lcd.write(addr.compile().into())?;
+            self.addr = Some((controller, addr.addr()));
+            return Ok(true);
+        }
+        let cell = self.cells[row][col];
+        lcd.write(Deliverable::Data(cell))?;
+        self.shadow[row][col] = cell;
+        self.addr = Some((controller, addr.addr() + 1));
+        self.next = row * COLS + col + 1;
+        Ok(self.next_dirty(geometry).is_some())
+    }
+
+    /// Finds the first changed cell from where the last flush stopped, wrapping around once.
+    /// Cells the display does not show are marked as sent on the way.
+    fn next_dirty(&mut self, geometry: Geometry) -> Option<(usize, usize, SetDdramAddr)> {
+        let (start, len) = (self.next, ROWS * COLS);
+        let (index, addr) = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find_map(|index| {
+                let (row, col) = (index / COLS, index % COLS);
+                let cell = self.cells[row][col];
+                if cell == self.shadow[row][col] {
+                    return None;
+                }
+                let addr = geometry.addr(row as u8, col as u8);
+                if addr.is_none() {
+                    self.shadow[row][col] = cell;
+                }
+                Some((index, addr?))
+            })?;
+        self.next = index;
+        Some((index / COLS, index % COLS, addr))
+    }
+}
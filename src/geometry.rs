@@ -0,0 +1,141 @@
+use crate::instr::SetDdramAddr;
+
+/// Base address of the second DDRAM line.
+const SECOND_LINE: u8 = 0x40;
+/// Characters a single controller holds in DDRAM.
+const MAX_COLS: u8 = 80;
+
+/// Layout of the visible characters in DDRAM.
+///
+/// Each row starts at its offset and runs for `cols` addresses, except on split displays such
+/// as most 16x1 modules, which are wired as two half rows and continue at the second line
+/// after `split` columns.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Geometry {
+    rows: u8,
+    cols: u8,
+    row_offsets: [u8; 4],
+    split: u8,
+    /// Rows driven by each controller.
+    controller_rows: u8,
+}
+
+impl Geometry {
+    pub const LCD_8X1: Self = Self::new(1, 8, [0x00, 0, 0, 0]);
+    /// A 16x1 display addressed as 8x2, with columns 8–15 at 0x40–0x47.
+    pub const LCD_16X1_SPLIT: Self = Self {
+        split: 8,
+        ..Self::new(1, 16, [0x00, 0, 0, 0])
+    };
+    pub const LCD_16X2: Self = Self::new(2, 16, [0x00, 0x40, 0, 0]);
+    pub const LCD_16X4: Self = Self::new(4, 16, [0x00, 0x40, 0x10, 0x50]);
+    pub const LCD_20X2: Self = Self::new(2, 20, [0x00, 0x40, 0, 0]);
+    pub const LCD_20X4: Self = Self::new(4, 20, [0x00, 0x40, 0x14, 0x54]);
+    pub const LCD_24X2: Self = Self::new(2, 24, [0x00, 0x40, 0, 0]);
+    pub const LCD_40X2: Self = Self::new(2, 40, [0x00, 0x40, 0, 0]);
+    /// A 40x4 display made of two 40x2 controllers, see [`DualEnable`](crate::dual::DualEnable).
+    pub const LCD_40X4: Self = Self {
+        controller_rows: 2,
+        ..Self::new(4, 40, [0x00, 0x40, 0x00, 0x40])
+    };
+
+    /// A layout of up to 4 rows starting at the given DDRAM addresses. Rows are clamped to
+    /// 1–4 and columns to 1–80, the most a controller holds, and offsets are masked to the 7
+    /// bits of an address.
+    #[inline]
+    pub const fn new(rows: u8, cols: u8, row_offsets: [u8; 4]) -> Self {
+        let rows = match rows {
+            0 => 1,
+            1..=4 => rows,
+            _ => 4,
+        };
+        let cols = match cols {
+            0 => 1,
+            1..=MAX_COLS => cols,
+            _ => MAX_COLS,
+        };
+        let mask = SetDdramAddr::MAX;
+        let row_offsets = [
+            row_offsets[0] & mask,
+            row_offsets[1] & mask,
+            row_offsets[2] & mask,
+            row_offsets[3] & mask,
+        ];
+        Self {
+            rows,
+            cols,
+            row_offsets,
+            split: cols,
+            controller_rows: rows,
+        }
+    }
+
+    #[inline]
+    pub const fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    #[inline]
+    pub const fn cols(&self) -> u8 {
+        self.cols
+    }
+
+    /// The controller showing a row, counting from 0.
+    #[inline]
+    pub const fn controller(&self, row: u8) -> u8 {
+        match self.controller_rows {
+            0 => 0,
+            rows => row / rows,
+        }
+    }
+
+    /// The DDRAM address of a position in its controller, or `None` if it lies outside the
+    /// display. Addresses past the last one wrap around like the address counter.
+    pub const fn addr(&self, row: u8, col: u8) -> Option<SetDdramAddr> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        let offset = self.row_offsets[row as usize];
+        if col < self.split {
+            Some(SetDdramAddr::new_masked(offset.wrapping_add(col)))
+        } else {
+            Some(SetDdramAddr::new_masked(
+                offset
+                    .wrapping_add(SECOND_LINE)
+                    .wrapping_add(col - self.split),
+            ))
+        }
+    }
+
+    /// The position shown at a DDRAM address such as [`State::addr`](crate::utils::State::addr)
+    /// of the first controller, or `None` if the address is not visible.
+    #[inline]
+    pub fn position(&self, addr: u8) -> Option<(u8, u8)> {
+        self.position_on(0, addr)
+    }
+
+    /// The position shown at a DDRAM address of `controller`, or `None` if the address is not
+    /// visible.
+    pub fn position_on(&self, controller: u8, addr: u8) -> Option<(u8, u8)> {
+        (0..self.rows)
+            .filter(|&row| self.controller(row) == controller)
+            .find_map(|row| {
+                let offset = self.row_offsets[row as usize];
+                let col = match addr.checked_sub(offset) {
+                    Some(col) if col < self.split => col,
+                    _ => addr
+                        .checked_sub(offset.checked_add(SECOND_LINE)?)?
+                        .checked_add(self.split)?,
+                };
+                (col < self.cols).then_some((row, col))
+            })
+    }
+}
+
+impl Default for Geometry {
+    /// 16x2, the most common module.
+    #[inline]
+    fn default() -> Self {
+        Self::LCD_16X2
+    }
+}
@@ -0,0 +1,275 @@
+use crate::instr::*;
+use crate::utils::{DelayMicros, State};
+use crate::{Interface, Lcd, ReadInterface};
+
+/// A user-defined character, given as rows of 5 pixels from top to bottom with the leftmost
+/// pixel in bit 4.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Glyph {
+    /// The last row is shared with the cursor.
+    Dots5x8([u8; 8]),
+    Dots5x10([u8; 10]),
+}
+
+impl Glyph {
+    #[inline]
+    pub const fn font(&self) -> Font {
+        match self {
+            Self::Dots5x8(_) => Font::Dots5x8,
+            Self::Dots5x10(_) => Font::Dots5x10,
+        }
+    }
+
+    #[inline]
+    pub fn rows(&self) -> &[u8] {
+        match self {
+            Self::Dots5x8(rows) => rows,
+            Self::Dots5x10(rows) => rows,
+        }
+    }
+}
+
+/// Number of glyphs CGRAM holds in a font.
+#[inline]
+pub const fn slots(font: Font) -> u8 {
+    match font {
+        Font::Dots5x8 => 8,
+        Font::Dots5x10 => 4,
+    }
+}
+
+/// Character code showing the glyph in `slot`. With 5x10 dots, bit 0 of the code is ignored
+/// and slots are numbered by bits 1–2.
+#[inline]
+pub const fn char_code(font: Font, slot: u8) -> u8 {
+    match font {
+        Font::Dots5x8 => slot % 8,
+        Font::Dots5x10 => (slot % 4) << 1,
+    }
+}
+
+/// Slot of the glyph shown by the CGRAM character code `code`.
+#[inline]
+pub(crate) const fn slot_of(font: Font, code: u8) -> u8 {
+    match font {
+        Font::Dots5x8 => code & 0x07,
+        Font::Dots5x10 => (code & 0x07) >> 1,
+    }
+}
+
+/// CGRAM address of the first row of the glyph in `slot`.
+#[inline]
+const fn cgram_addr(font: Font, slot: u8) -> SetCgramAddr {
+    match font {
+        Font::Dots5x8 => SetCgramAddr::new_masked((slot % 8) << 3),
+        Font::Dots5x10 => SetCgramAddr::new_masked((slot % 4) << 4),
+    }
+}
+
+/// Rows of CGRAM taken by a glyph, the eleventh one of 5x10 dots being shared with the cursor.
+#[inline]
+const fn cgram_rows(font: Font) -> usize {
+    match font {
+        Font::Dots5x8 => 8,
+        Font::Dots5x10 => 11,
+    }
+}
+
+impl<I: Interface, D: DelayMicros> Lcd<I, D> {
+    /// Uploads `glyph` into `slot` of CGRAM, then moves the cursor to `addr`. Slots past the
+    /// last one of the display's font wrap around.
+    ///
+    /// The glyph takes the font the display was initialized with, so a 5x10 glyph loses its
+    /// bottom rows on a 5x8 display and a 5x8 glyph gets blank rows below on a 5x10 one.
+    pub fn upload_glyph_and_seek(
+        &mut self,
+        slot: u8,
+        glyph: &Glyph,
+        addr: SetDdramAddr,
+    ) -> nb::Result<(), I::Error> {
+        let font = self.font;
+        nb::block!(self.write(cgram_addr(font, slot).compile().into()))?;
+        let rows = glyph.rows();
+        (0..cgram_rows(font)).try_for_each(|i| {
+            let row = rows.get(i).map_or(0, |row| row & 0x1F);
+            nb::block!(self.write(Deliverable::Data(row)))
+        })?;
+        nb::block!(self.write(addr.compile().into()))?;
+        Ok(())
+    }
+
+    /// Writes the glyph in `slot` at the cursor, in the font the display was initialized with.
+    pub fn print_glyph(&mut self, slot: u8) -> nb::Result<(), I::Error> {
+        self.write(Deliverable::Data(char_code(self.font, slot)))
+    }
+}
+
+impl<I: ReadInterface, D: DelayMicros> Lcd<I, D> {
+    /// Uploads `glyph` into `slot` of CGRAM, leaving the cursor where it was.
+    pub fn upload_glyph(&mut self, slot: u8, glyph: &Glyph) -> nb::Result<(), I::Error> {
+        let addr = nb::block!(self.poll_state())?.addr();
+        self.upload_glyph_and_seek(slot, glyph, SetDdramAddr::new_masked(addr))
+    }
+
+    /// The state once the controller is no longer busy, so that the address counter has
+    /// settled.
+    fn poll_state(&mut self) -> nb::Result<State, I::Error> {
+        let state = self.state()?;
+        if state.busy() {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(state)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<K> {
+    id: K,
+    last_used: u32,
+    /// Number of times the glyph is shown on the display.
+    visible: u16,
+}
+
+/// Maps any number of logical glyph IDs onto the CGRAM slots of an [`Lcd`], uploading glyphs
+/// on demand and evicting the least recently used one that is not on screen.
+///
+/// The cache only knows a glyph has left the screen when told through [`GlyphCache::forget`]
+/// or [`GlyphCache::forget_all`].
+#[derive(Debug, Clone)]
+pub struct GlyphCache<K> {
+    entries: [Option<Entry<K>>; 8],
+    clock: u32,
+}
+
+#[derive(Debug)]
+pub enum GlyphCacheError<E> {
+    InterfaceError(E),
+    /// Every slot holds a glyph that is on screen.
+    NoFreeSlot,
+}
+
+impl<E> From<E> for GlyphCacheError<E> {
+    #[inline]
+    fn from(value: E) -> Self {
+        Self::InterfaceError(value)
+    }
+}
+
+impl<K: PartialEq + Copy> Default for GlyphCache<K> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialEq + Copy> GlyphCache<K> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; 8],
+            clock: 0,
+        }
+    }
+
+    /// The slot currently holding `id`, if any.
+    pub fn slot(&self, id: K) -> Option<u8> {
+        self.entries
+            .iter()
+            .position(|entry| matches!(entry, Some(entry) if entry.id == id))
+            .map(|slot| slot as u8)
+    }
+
+    /// Writes the glyph `id` at `addr`, uploading `glyph` first if it is not cached, and
+    /// counts it as shown. On displays made of several controllers, `addr` refers to the one
+    /// selected through [`Interface::select`].
+    pub fn print_at<I: Interface, D: DelayMicros>(
+        &mut self,
+        lcd: &mut Lcd<I, D>,
+        id: K,
+        glyph: &Glyph,
+        addr: SetDdramAddr,
+    ) -> nb::Result<(), GlyphCacheError<I::Error>> {
+        let slot = match self.slot(id) {
+            Some(slot) => {
+                nb::block!(lcd.write(addr.compile().into()))
+                    .map_err(GlyphCacheError::InterfaceError)?;
+                slot
+            }
+            None => {
+                let slot = self
+                    .victim(slots(lcd.font))
+                    .ok_or(nb::Error::Other(GlyphCacheError::NoFreeSlot))?;
+                lcd.upload_glyph_and_seek(slot, glyph, addr)
+                    .map_err(|e| e.map(GlyphCacheError::InterfaceError))?;
+                self.entries[slot as usize] = Some(Entry {
+                    id,
+                    last_used: 0,
+                    visible: 0,
+                });
+                slot
+            }
+        };
+        nb::block!(lcd.print_glyph(slot)).map_err(GlyphCacheError::InterfaceError)?;
+        self.clock = self.clock.wrapping_add(1);
+        if let Some(entry) = &mut self.entries[slot as usize] {
+            entry.last_used = self.clock;
+            entry.visible = entry.visible.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// Writes the glyph `id` at the cursor, uploading `glyph` first if it is not cached, and
+    /// counts it as shown.
+    pub fn print<I: ReadInterface, D: DelayMicros>(
+        &mut self,
+        lcd: &mut Lcd<I, D>,
+        id: K,
+        glyph: &Glyph,
+    ) -> nb::Result<(), GlyphCacheError<I::Error>> {
+        let addr = nb::block!(lcd.poll_state())
+            .map_err(GlyphCacheError::InterfaceError)?
+            .addr();
+        self.print_at(lcd, id, glyph, SetDdramAddr::new_masked(addr))
+    }
+
+    /// Counts one occurrence of the glyph `id` as overwritten on the display.
+    pub fn forget(&mut self, id: K) {
+        if let Some(slot) = self.slot(id) {
+            self.forget_slot(slot);
+        }
+    }
+
+    /// Counts one occurrence of the glyph in `slot` as overwritten on the display.
+    pub fn forget_slot(&mut self, slot: u8) {
+        if let Some(Some(entry)) = self.entries.get_mut(slot as usize) {
+            entry.visible = entry.visible.saturating_sub(1);
+        }
+    }
+
+    /// Counts every glyph as gone from the display, such as after [`Clear`].
+    pub fn forget_all(&mut self) {
+        self.entries
+            .iter_mut()
+            .flatten()
+            .for_each(|entry| entry.visible = 0);
+    }
+
+    /// An empty slot, or else the least recently used slot that is not on screen.
+    fn victim(&self, slots: u8) -> Option<u8> {
+        let entries = &self.entries[..slots as usize];
+        entries
+            .iter()
+            .position(Option::is_none)
+            .or_else(|| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(slot, entry)| entry.as_ref().map(|entry| (slot, entry)))
+                    .filter(|(_, entry)| entry.visible == 0)
+                    .max_by_key(|(_, entry)| self.clock.wrapping_sub(entry.last_used))
+                    .map(|(slot, _)| slot)
+            })
+            .map(|slot| slot as u8)
+    }
+}
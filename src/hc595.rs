@@ -0,0 +1,149 @@
+use hal::spi::SpiDevice;
+
+use crate::instr::*;
+use crate::utils::{self, Clock, Countdown, DelayMicros};
+use crate::{hal, Interface};
+
+/// Assignment of the shift register outputs Q0–Q7 to the controller, given as output numbers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Hc595Mapping {
+    pub register_selection: u8,
+    pub enable: u8,
+    /// Output switching the backlight on when high, if any.
+    pub backlight: Option<u8>,
+    /// Outputs wired to DB4–DB7, in that order.
+    pub data: [u8; 4],
+}
+
+impl Hc595Mapping {
+    const fn data_mask(&self, nibble: u8) -> u8 {
+        utils::data_mask(&self.data, nibble) as u8
+    }
+}
+
+impl Default for Hc595Mapping {
+    /// RS on Q0, E on Q1, backlight on Q2 and DB4–DB7 on Q4–Q7.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            register_selection: 0,
+            enable: 1,
+            backlight: Some(2),
+            data: [4, 5, 6, 7],
+        }
+    }
+}
+
+/// An [`Interface`] over a 74HC595 shift register, which drives RS, E and DB4–DB7 with RW
+/// tied to ground. The register's RCLK is wired as the chip select of `SPI`, so that its
+/// rising edge at the end of every transaction latches the shifted byte.
+///
+/// As the controller cannot be read back, every transfer waits out its execution time, either
+/// with the delay or against a clock given through [`Hc595::with_clock`].
+pub struct Hc595<SPI: SpiDevice> {
+    spi: SPI,
+    mapping: Hc595Mapping,
+    backlight: bool,
+    countdown: Countdown,
+}
+
+impl<SPI: SpiDevice> Hc595<SPI> {
+    #[inline]
+    pub fn new(spi: SPI, mapping: Hc595Mapping) -> Self {
+        Self {
+            spi,
+            mapping,
+            backlight: true,
+            countdown: Countdown::default(),
+        }
+    }
+
+    /// Measures execution times against `clock` instead of spending them with the delay.
+    #[inline]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.countdown.set_clock(Some(clock));
+        self
+    }
+
+    #[inline]
+    pub fn backlight(&self) -> bool {
+        self.backlight
+    }
+
+    #[inline]
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    pub fn set_backlight(&mut self, on: bool) -> Result<(), SPI::Error> {
+        self.backlight = on;
+        let outputs = self.outputs(false);
+        self.shift(outputs)
+    }
+
+    fn outputs(&self, register_selection: bool) -> u8 {
+        let backlight = match self.mapping.backlight {
+            Some(line) => (self.backlight as u8) << line,
+            None => 0,
+        };
+        (register_selection as u8) << self.mapping.register_selection | backlight
+    }
+
+    #[inline]
+    fn shift(&mut self, outputs: u8) -> Result<(), SPI::Error> {
+        self.spi.write(&[outputs])
+    }
+
+    fn strobe(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        register_selection: bool,
+        nibble: u8,
+    ) -> Result<(), SPI::Error> {
+        let outputs = self.outputs(register_selection) | self.mapping.data_mask(nibble);
+        // RS and the data lines settle with E low before the pulse.
+        self.shift(outputs)?;
+        self.shift(outputs | 1 << self.mapping.enable)?;
+        delay.delay_us(1);
+        self.shift(outputs)?;
+        delay.delay_us(1);
+        Ok(())
+    }
+}
+
+impl<SPI: SpiDevice> Interface for Hc595<SPI> {
+    type Error = SPI::Error;
+
+    const DATA_LENGTH: DataLength = DataLength::Four;
+
+    fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        nibble: u8,
+    ) -> Result<(), Self::Error> {
+        self.strobe(delay, false, nibble)
+    }
+
+    fn write(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> nb::Result<(), Self::Error> {
+        self.countdown
+            .poll(delay)
+            .map_err(|_| nb::Error::WouldBlock)?;
+        let (register_selection, datum) = match deliverable {
+            Deliverable::Instr(CompiledInstr(datum)) => (false, datum),
+            Deliverable::Data(datum) => (true, datum),
+        };
+        self.strobe(delay, register_selection, datum >> 4)?;
+        self.strobe(delay, register_selection, datum & 0x0F)?;
+        self.countdown.start(deliverable.execution_time_us());
+        Ok(())
+    }
+
+    #[inline]
+    fn clock(&self) -> Option<Clock> {
+        self.countdown.clock()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::instr::*;
+use crate::utils::{Countdown, DelayMicros};
+use crate::{Interface, Lcd};
+
+/// Time to wait after Vcc rises to 2.7 V before the first instruction.
+pub(crate) const POWER_ON_US: u32 = 40_000;
+/// Time to wait after the first wake-up nibble.
+pub(crate) const FIRST_WAKE_US: u32 = 4_100;
+/// Time to wait after every later nibble of the sequence.
+pub(crate) const WAKE_US: u32 = 100;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum InitStep {
+    PowerOn,
+    SecondWake,
+    ThirdWake,
+    FourBitMode,
+    FunctionSet,
+    DisplayOff,
+    Clear,
+    EntryMode,
+    Done,
+}
+
+/// Power-on initialization of an [`Lcd`], following the "initializing by instruction"
+/// procedure of the HD44780 datasheet.
+///
+/// Every call to [`LcdInit::poll`] performs at most one bus transfer or waits at most
+/// 100 µs, returning [`nb::Error::WouldBlock`] until the sequence has completed, so it can be
+/// driven from a main loop or a timer interrupt. When the interface has a clock, see
+/// [`Interface::clock`], the waits of the sequence are measured against it and polls do not
+/// wait at all.
+pub struct LcdInit<I: Interface, D: DelayMicros> {
+    lcd: Lcd<I, D>,
+    function_set: FunctionSet,
+    entry_mode: EntryModeSet,
+    step: InitStep,
+    countdown: Countdown,
+}
+
+impl<I: Interface, D: DelayMicros> LcdInit<I, D> {
+    pub fn new(lcd: Lcd<I, D>, function_set: FunctionSet, entry_mode: EntryModeSet) -> Self {
+        let mut countdown = Countdown::default();
+        countdown.set_clock(lcd.interface.clock());
+        countdown.start(POWER_ON_US);
+        // The controller ignores 5x10 dots in two-line mode.
+        let font = match function_set.lines {
+            Lines::One => function_set.font,
+            Lines::Two => Font::Dots5x8,
+        };
+        Self {
+            lcd: Lcd { font, ..lcd },
+            function_set: FunctionSet {
+                data_length: I::DATA_LENGTH,
+                ..function_set
+            },
+            entry_mode,
+            step: InitStep::PowerOn,
+            countdown,
+        }
+    }
+
+    pub fn poll(&mut self) -> nb::Result<(), I::Error> {
+        let Lcd {
+            interface, delay, ..
+        } = &mut self.lcd;
+        if self.countdown.poll(delay).is_err() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.step = match self.step {
+            InitStep::PowerOn => {
+                interface.write_nibble(delay, 0x3)?;
+                self.countdown.start(FIRST_WAKE_US);
+                InitStep::SecondWake
+            }
+            InitStep::SecondWake => {
+                interface.write_nibble(delay, 0x3)?;
+                self.countdown.start(WAKE_US);
+                InitStep::ThirdWake
+            }
+            InitStep::ThirdWake => {
+                interface.write_nibble(delay, 0x3)?;
+                self.countdown.start(WAKE_US);
+                match I::DATA_LENGTH {
+                    DataLength::Four => InitStep::FourBitMode,
+                    DataLength::Eight => InitStep::FunctionSet,
+                }
+            }
+            InitStep::FourBitMode => {
+                interface.write_nibble(delay, 0x2)?;
+                self.countdown.start(WAKE_US);
+                InitStep::FunctionSet
+            }
+            InitStep::FunctionSet => {
+                interface.write(delay, self.function_set.compile().into())?;
+                InitStep::DisplayOff
+            }
+            InitStep::DisplayOff => {
+                interface.write(delay, DisplayControl::default().compile().into())?;
+                InitStep::Clear
+            }
+            InitStep::Clear => {
+                interface.write(delay, Clear::compile().into())?;
+                InitStep::EntryMode
+            }
+            InitStep::EntryMode => {
+                interface.write(delay, self.entry_mode.compile().into())?;
+                InitStep::Done
+            }
+            InitStep::Done => InitStep::Done,
+        };
+        match self.step {
+            InitStep::Done => Ok(()),
+            _ => Err(nb::Error::WouldBlock),
+        }
+    }
+
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.step == InitStep::Done
+    }
+
+    /// Returns the initialized [`Lcd`], or gives back `self` if the sequence has not completed.
+    pub fn finish(self) -> Result<Lcd<I, D>, Self> {
+        if self.is_done() {
+            Ok(self.lcd)
+        } else {
+            Err(self)
+        }
+    }
+}
@@ -2,16 +2,46 @@
 #[repr(transparent)]
 pub struct CompiledInstr(pub(crate) u8);
 
+impl CompiledInstr {
+    /// Time in microseconds the controller takes to execute this instruction.
+    pub const fn execution_time_us(&self) -> u32 {
+        match self.0 {
+            0x01 => Clear::EXECUTION_TIME_US,
+            0x02 | 0x03 => ReturnHome::EXECUTION_TIME_US,
+            _ => 37,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Deliverable {
     Instr(CompiledInstr),
     Data(u8),
 }
 
+impl Deliverable {
+    /// Time in microseconds after which the controller accepts the next transfer.
+    pub const fn execution_time_us(&self) -> u32 {
+        match self {
+            Self::Instr(instr) => instr.execution_time_us(),
+            Self::Data(_) => 37,
+        }
+    }
+}
+
+impl From<CompiledInstr> for Deliverable {
+    #[inline]
+    fn from(value: CompiledInstr) -> Self {
+        Self::Instr(value)
+    }
+}
+
 #[derive(Debug)]
 pub struct Clear;
 
 impl Clear {
+    pub const EXECUTION_TIME_US: u32 = 1_520;
+
     pub const fn compile() -> CompiledInstr {
         CompiledInstr(0x01)
     }
@@ -21,7 +51,215 @@ impl Clear {
 pub struct ReturnHome;
 
 impl ReturnHome {
+    pub const EXECUTION_TIME_US: u32 = 1_520;
+
     pub const fn compile() -> CompiledInstr {
         CompiledInstr(0x02)
     }
 }
+
+/// Direction in which the address counter moves, or the cursor/display shifts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EntryModeSet {
+    /// `Right` increments the address counter after each data access, `Left` decrements it.
+    pub direction: Direction,
+    /// Shifts the entire display along with the cursor on each data write.
+    pub shift_display: bool,
+}
+
+impl EntryModeSet {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const fn compile(&self) -> CompiledInstr {
+        let direction = match self.direction {
+            Direction::Left => 0x00,
+            Direction::Right => 0x02,
+        };
+        CompiledInstr(0x04 | direction | self.shift_display as u8)
+    }
+}
+
+impl Default for EntryModeSet {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            direction: Direction::Right,
+            shift_display: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct DisplayControl {
+    pub display: bool,
+    pub cursor: bool,
+    pub blink: bool,
+}
+
+impl DisplayControl {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const fn compile(&self) -> CompiledInstr {
+        CompiledInstr(
+            0x08 | (self.display as u8) << 2 | (self.cursor as u8) << 1 | self.blink as u8,
+        )
+    }
+}
+
+/// What a [`CursorDisplayShift`] moves without touching DDRAM contents.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ShiftTarget {
+    Cursor,
+    Display,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CursorDisplayShift {
+    pub target: ShiftTarget,
+    pub direction: Direction,
+}
+
+impl CursorDisplayShift {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const fn compile(&self) -> CompiledInstr {
+        let target = match self.target {
+            ShiftTarget::Cursor => 0x00,
+            ShiftTarget::Display => 0x08,
+        };
+        let direction = match self.direction {
+            Direction::Left => 0x00,
+            Direction::Right => 0x04,
+        };
+        CompiledInstr(0x10 | target | direction)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DataLength {
+    Four,
+    Eight,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lines {
+    One,
+    Two,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Font {
+    Dots5x8,
+    /// Only available in one-line mode, the controller ignores it otherwise.
+    Dots5x10,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FunctionSet {
+    pub data_length: DataLength,
+    pub lines: Lines,
+    pub font: Font,
+}
+
+impl FunctionSet {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const fn compile(&self) -> CompiledInstr {
+        let data_length = match self.data_length {
+            DataLength::Four => 0x00,
+            DataLength::Eight => 0x10,
+        };
+        let lines = match self.lines {
+            Lines::One => 0x00,
+            Lines::Two => 0x08,
+        };
+        let font = match self.font {
+            Font::Dots5x8 => 0x00,
+            Font::Dots5x10 => 0x04,
+        };
+        CompiledInstr(0x20 | data_length | lines | font)
+    }
+}
+
+impl Default for FunctionSet {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            data_length: DataLength::Four,
+            lines: Lines::Two,
+            font: Font::Dots5x8,
+        }
+    }
+}
+
+/// Set CGRAM Address, holding a 6-bit address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SetCgramAddr(u8);
+
+impl SetCgramAddr {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const MAX: u8 = 0x3F;
+
+    /// Returns `None` if `addr` does not fit in 6 bits.
+    pub const fn new(addr: u8) -> Option<Self> {
+        if addr <= Self::MAX {
+            Some(Self(addr))
+        } else {
+            None
+        }
+    }
+
+    /// Discards the bits of `addr` above bit 5.
+    pub const fn new_masked(addr: u8) -> Self {
+        Self(addr & Self::MAX)
+    }
+
+    #[inline]
+    pub const fn addr(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn compile(&self) -> CompiledInstr {
+        CompiledInstr(0x40 | self.0)
+    }
+}
+
+/// Set DDRAM Address, holding a 7-bit address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SetDdramAddr(u8);
+
+impl SetDdramAddr {
+    pub const EXECUTION_TIME_US: u32 = 37;
+
+    pub const MAX: u8 = 0x7F;
+
+    /// Returns `None` if `addr` does not fit in 7 bits.
+    pub const fn new(addr: u8) -> Option<Self> {
+        if addr <= Self::MAX {
+            Some(Self(addr))
+        } else {
+            None
+        }
+    }
+
+    /// Discards bit 7 of `addr`.
+    pub const fn new_masked(addr: u8) -> Self {
+        Self(addr & Self::MAX)
+    }
+
+    #[inline]
+    pub const fn addr(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn compile(&self) -> CompiledInstr {
+        CompiledInstr(0x80 | self.0)
+    }
+}
@@ -0,0 +1,127 @@
+use crate::charset::Codes;
+
+/// Dakuten, the voiced sound mark shown in a cell of its own.
+const DAKUTEN: u8 = 0xDE;
+/// Handakuten, the semi-voiced sound mark shown in a cell of its own.
+const HANDAKUTEN: u8 = 0xDF;
+
+/// A00 codes of the katakana U+30A1–U+30FA, followed by their sound mark or 0. Small ヮ, ヵ
+/// and ヶ and the obsolete ヰ and ヱ fall back to the closest half-width katakana.
+const KATAKANA: [[u8; 2]; 90] = [
+    [0xA7, 0],          // ァ
+    [0xB1, 0],          // ア
+    [0xA8, 0],          // ィ
+    [0xB2, 0],          // イ
+    [0xA9, 0],          // ゥ
+    [0xB3, 0],          // ウ
+    [0xAA, 0],          // ェ
+    [0xB4, 0],          // エ
+    [0xAB, 0],          // ォ
+    [0xB5, 0],          // オ
+    [0xB6, 0],          // カ
+    [0xB6, DAKUTEN],    // ガ
+    [0xB7, 0],          // キ
+    [0xB7, DAKUTEN],    // ギ
+    [0xB8, 0],          // ク
+    [0xB8, DAKUTEN],    // グ
+    [0xB9, 0],          // ケ
+    [0xB9, DAKUTEN],    // ゲ
+    [0xBA, 0],          // コ
+    [0xBA, DAKUTEN],    // ゴ
+    [0xBB, 0],          // サ
+    [0xBB, DAKUTEN],    // ザ
+    [0xBC, 0],          // シ
+    [0xBC, DAKUTEN],    // ジ
+    [0xBD, 0],          // ス
+    [0xBD, DAKUTEN],    // ズ
+    [0xBE, 0],          // セ
+    [0xBE, DAKUTEN],    // ゼ
+    [0xBF, 0],          // ソ
+    [0xBF, DAKUTEN],    // ゾ
+    [0xC0, 0],          // タ
+    [0xC0, DAKUTEN],    // ダ
+    [0xC1, 0],          // チ
+    [0xC1, DAKUTEN],    // ヂ
+    [0xAF, 0],          // ッ
+    [0xC2, 0],          // ツ
+    [0xC2, DAKUTEN],    // ヅ
+    [0xC3, 0],          // テ
+    [0xC3, DAKUTEN],    // デ
+    [0xC4, 0],          // ト
+    [0xC4, DAKUTEN],    // ド
+    [0xC5, 0],          // ナ
+    [0xC6, 0],          // ニ
+    [0xC7, 0],          // ヌ
+    [0xC8, 0],          // ネ
+    [0xC9, 0],          // ノ
+    [0xCA, 0],          // ハ
+    [0xCA, DAKUTEN],    // バ
+    [0xCA, HANDAKUTEN], // パ
+    [0xCB, 0],          // ヒ
+    [0xCB, DAKUTEN],    // ビ
+    [0xCB, HANDAKUTEN], // ピ
+    [0xCC, 0],          // フ
+    [0xCC, DAKUTEN],    // ブ
+    [0xCC, HANDAKUTEN], // プ
+    [0xCD, 0],          // ヘ
+    [0xCD, DAKUTEN],    // ベ
+    [0xCD, HANDAKUTEN], // ペ
+    [0xCE, 0],          // ホ
+    [0xCE, DAKUTEN],    // ボ
+    [0xCE, HANDAKUTEN], // ポ
+    [0xCF, 0],          // マ
+    [0xD0, 0],          // ミ
+    [0xD1, 0],          // ム
+    [0xD2, 0],          // メ
+    [0xD3, 0],          // モ
+    [0xAC, 0],          // ャ
+    [0xD4, 0],          // ヤ
+    [0xAD, 0],          // ュ
+    [0xD5, 0],          // ユ
+    [0xAE, 0],          // ョ
+    [0xD6, 0],          // ヨ
+    [0xD7, 0],          // ラ
+    [0xD8, 0],          // リ
+    [0xD9, 0],          // ル
+    [0xDA, 0],          // レ
+    [0xDB, 0],          // ロ
+    [0xDC, 0],          // ヮ
+    [0xDC, 0],          // ワ
+    [0xB2, 0],          // ヰ
+    [0xB4, 0],          // ヱ
+    [0xA6, 0],          // ヲ
+    [0xDD, 0],          // ン
+    [0xB3, DAKUTEN],    // ヴ
+    [0xB6, 0],          // ヵ
+    [0xB9, 0],          // ヶ
+    [0xDC, DAKUTEN],    // ヷ
+    [0xB2, DAKUTEN],    // ヸ
+    [0xB4, DAKUTEN],    // ヹ
+    [0xA6, DAKUTEN],    // ヺ
+];
+
+/// Translates full-width katakana and hiragana into half-width katakana codes of the
+/// [`Rom::A00`](crate::charset::Rom::A00), splitting voiced syllables into the base kana and
+/// a separate ﾞ or ﾟ.
+pub fn encode(c: char) -> Option<Codes> {
+    let code = match c {
+        '。' => 0xA1,
+        '「' => 0xA2,
+        '」' => 0xA3,
+        '、' => 0xA4,
+        '・' => 0xA5,
+        'ー' => 0xB0,
+        '゛' => DAKUTEN,
+        '゜' => HANDAKUTEN,
+        // Hiragana sit 0x60 below the matching katakana.
+        'ぁ'..='ゖ' => return encode(char::from_u32(c as u32 + 0x60)?),
+        'ァ'..='ヺ' => {
+            return match KATAKANA[(c as u32 - 0x30A1) as usize] {
+                [code, 0] => Some(Codes::one(code)),
+                [code, mark] => Some(Codes::two(code, mark)),
+            };
+        }
+        _ => return None,
+    };
+    Some(Codes::one(code))
+}
@@ -1,46 +1,328 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub use bitvec;
 pub use embedded_hal as hal;
+#[cfg(feature = "eh02")]
+pub use embedded_hal_02 as hal02;
+#[cfg(feature = "async")]
+pub use embedded_hal_async as hal_async;
 pub use nb;
 pub use ufmt;
 
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod bus;
+pub mod charset;
+#[cfg(feature = "eh02")]
+pub mod compat;
+pub mod dual;
+#[cfg(feature = "std")]
+pub mod emulator;
+pub mod framebuffer;
+pub mod geometry;
+pub mod glyph;
+pub mod hc595;
+pub mod init;
 pub mod instr;
+pub mod kana;
+pub mod mcp230xx;
+pub mod pcf8574;
+pub mod shared;
+pub mod text;
+pub mod trace;
 pub mod utils;
 
 use bitvec::prelude::*;
-use hal::digital::v2::{OutputPin, PinState};
+use core::convert::Infallible;
+use hal::digital::{OutputPin, PinState};
 use ufmt::uWrite;
 
+use crate::charset::Charset;
+use crate::geometry::Geometry;
 use crate::instr::*;
 use crate::utils::DelayMicros;
-use crate::utils::State;
+use crate::utils::{Clock, Countdown, State};
+
+/// Time RS and RW must settle before E rises.
+pub(crate) const ADDRESS_SETUP_NS: u32 = 60;
 
+/// The data lines of the controller, either DB4–DB7 or DB0–DB7 depending on
+/// [`DataBus::DATA_LENGTH`]. Pin states are passed lowest data line first.
 pub trait DataBus: Sized {
     type Error;
 
+    const DATA_LENGTH: DataLength = DataLength::Four;
+
     fn write_pins_now(
         &mut self,
         states: impl ExactSizeIterator<Item = PinState>,
     ) -> Result<(), Self::Error>;
-    fn read_pins_now(&mut self) -> Result<[PinState; 4], Self::Error>;
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error>;
+
+    /// Releases the data lines for the controller to drive, called before RW goes high.
+    #[inline]
+    fn set_input_mode(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Drives the data lines again, called after RW has gone back low.
+    #[inline]
+    fn set_output_mode(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The RW line, either an [`OutputPin`] or [`Grounded`] when the module is wired write-only.
+pub trait ReadWritePin {
+    type Error;
+
+    /// Whether the controller can be read back. When it cannot, [`LcdPins`] never reads the
+    /// busy flag and instead waits out the execution time of every transfer. With a clock
+    /// given through [`LcdPins::with_clock`], the following calls to [`Interface::write`]
+    /// return [`nb::Error::WouldBlock`] until that time has passed. Without one, the time is
+    /// spent with the delay, in slices of at most 100 µs inside those calls, and time passing
+    /// between them does not shorten the wait.
+    const READABLE: bool;
+
+    fn set_read(&mut self) -> Result<(), Self::Error>;
+    fn set_write(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<P: OutputPin> ReadWritePin for P {
+    type Error = P::Error;
+
+    const READABLE: bool = true;
+
+    #[inline]
+    fn set_read(&mut self) -> Result<(), Self::Error> {
+        self.set_high()
+    }
+
+    #[inline]
+    fn set_write(&mut self) -> Result<(), Self::Error> {
+        self.set_low()
+    }
+}
+
+/// Stands in for an RW line tied to ground. The data bus is then never read.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Grounded;
+
+impl ReadWritePin for Grounded {
+    type Error = Infallible;
+
+    const READABLE: bool = false;
+
+    #[inline]
+    fn set_read(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn set_write(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The E line, either an [`OutputPin`] or [`DualEnable`](dual::DualEnable) for displays
+/// made of several controllers sharing the other lines.
+pub trait EnablePin {
+    type Error;
+
+    /// Number of controllers, each with an E line of its own.
+    const CONTROLLERS: u8;
+
+    /// Chooses the controller the next pulses go to, or every controller for `None`.
+    fn select(&mut self, controller: Option<u8>);
+    fn set_high(&mut self) -> Result<(), Self::Error>;
+    fn set_low(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<P: OutputPin> EnablePin for P {
+    type Error = P::Error;
+
+    const CONTROLLERS: u8 = 1;
+
+    #[inline]
+    fn select(&mut self, _controller: Option<u8>) {}
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        OutputPin::set_high(self)
+    }
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        OutputPin::set_low(self)
+    }
+}
+
+/// A way of driving the controller, such as [`LcdPins`] or an I/O expander backpack.
+pub trait Interface {
+    type Error;
+
+    const DATA_LENGTH: DataLength;
+
+    /// Writes a single nibble to DB4–DB7 as an instruction in one transfer, without checking
+    /// the busy flag, as required during initialization while the interface is still in an
+    /// unknown mode.
+    fn write_nibble(&mut self, delay: &mut impl DelayMicros, nibble: u8)
+        -> Result<(), Self::Error>;
+
+    /// Returns [`nb::Error::WouldBlock`] while the controller is still executing the previous
+    /// transfer.
+    fn write(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> nb::Result<(), Self::Error>;
+
+    /// Directs DDRAM transfers to one of the controllers of a display made of several, such
+    /// as a 40x4 display. Instructions other than Set DDRAM Address and CGRAM transfers go to
+    /// every controller, and Clear and Return Home select the first one again. The cursor and
+    /// blinking of Display On/Off Control only show on the selected controller, and follow
+    /// it with the next write.
+    #[inline]
+    fn select(&mut self, _controller: u8) {}
+
+    /// The controller DDRAM transfers go to.
+    #[inline]
+    fn selected(&self) -> u8 {
+        0
+    }
+
+    /// The clock execution times are measured against, if the interface was given one.
+    #[inline]
+    fn clock(&self) -> Option<Clock> {
+        None
+    }
+}
+
+/// An [`Interface`] able to read back the busy flag and address counter.
+pub trait ReadInterface: Interface {
+    fn state(&mut self, delay: &mut impl DelayMicros) -> Result<State, Self::Error>;
+}
+
+/// One step of writing a deliverable, as planned by [`LcdPins::plan`] for both the blocking
+/// and the async interface, which only differ in how they toggle the pins and wait.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Phase {
+    /// The last Display On/Off Control, sent to every controller with the cursor and blinking
+    /// only on the selected one.
+    DisplayControl,
+    /// A deliverable sent to one controller, or to every controller for `None`.
+    Transfer(Option<u8>, Deliverable),
+}
+
+impl Phase {
+    /// The controller that must be done executing before the phase, or every one for `None`.
+    #[inline]
+    pub(crate) fn target(self) -> Option<u8> {
+        match self {
+            Self::DisplayControl => None,
+            Self::Transfer(target, _) => target,
+        }
+    }
+}
+
+/// What [`LcdPins`] keeps track of about the controllers between transfers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ControllerState {
+    pub(crate) countdown: Countdown,
+    /// Controller DDRAM transfers go to.
+    pub(crate) selected: u8,
+    /// Whether data goes to CGRAM, and so to every controller.
+    pub(crate) cgram: bool,
+    /// The last Display On/Off Control.
+    pub(crate) display_control: u8,
+    /// Controller showing the cursor and blinking of `display_control`.
+    pub(crate) cursor_on: u8,
+}
+
+impl Default for ControllerState {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            countdown: Countdown::default(),
+            selected: 0,
+            cgram: false,
+            display_control: DisplayControl::default().compile().0,
+            cursor_on: 0,
+        }
+    }
+}
+
+impl ControllerState {
+    /// Selects one of `controllers`, the last one past their number.
+    #[inline]
+    pub(crate) fn select(&mut self, controller: u8, controllers: u8) {
+        self.selected = controller.min(controllers - 1);
+    }
+
+    /// The controller `deliverable` goes to, or `None` for every controller, keeping track of
+    /// where data goes.
+    fn route(&mut self, deliverable: Deliverable) -> Option<u8> {
+        match deliverable {
+            Deliverable::Instr(CompiledInstr(datum)) if datum & 0x80 != 0 => {
+                self.cgram = false;
+                Some(self.selected)
+            }
+            Deliverable::Instr(CompiledInstr(datum)) if datum & 0x40 != 0 => {
+                self.cgram = true;
+                None
+            }
+            Deliverable::Instr(CompiledInstr(0x01..=0x03)) => {
+                self.cgram = false;
+                self.selected = 0;
+                None
+            }
+            Deliverable::Instr(CompiledInstr(datum @ 0x08..=0x0F)) => {
+                self.display_control = datum;
+                None
+            }
+            Deliverable::Instr(_) => None,
+            Deliverable::Data(_) if self.cgram => None,
+            Deliverable::Data(_) => Some(self.selected),
+        }
+    }
+
+    /// The phases of writing `deliverable`, keeping track of where data goes. When the
+    /// cursor or blinking shows on another controller than the selected one, they first move
+    /// to the selected one.
+    pub(crate) fn plan(&mut self, deliverable: Deliverable) -> [Option<Phase>; 2] {
+        let target = self.route(deliverable);
+        let display_control = matches!(deliverable, Deliverable::Instr(CompiledInstr(0x08..=0x0F)));
+        let cursor_moved = self.display_control & 0x03 != 0 && self.cursor_on != self.selected;
+        let phase = match display_control {
+            true => Phase::DisplayControl,
+            false => Phase::Transfer(target, deliverable),
+        };
+        [
+            (cursor_moved && !display_control).then_some(Phase::DisplayControl),
+            Some(phase),
+        ]
+    }
 }
 
-pub struct LcdPins<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> {
+pub struct LcdPins<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> {
     pub(crate) register_selection: RS,
     pub(crate) read_write: RW,
-    enable: E,
+    pub(crate) enable: E,
     pub(crate) data_bus: DB,
+    pub(crate) controller: ControllerState,
 }
 
-pub enum LcdError<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> {
+pub enum LcdError<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> {
     RegisterSelectionError(RS::Error),
     ReadWriteError(RW::Error),
     EnableError(E::Error),
     DataBusError(DB::Error),
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> LcdPins<RS, RW, E, DB> {
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> LcdPins<RS, RW, E, DB> {
     #[inline]
     pub fn new(register_selection: RS, read_write: RW, enable: E, data_bus: DB) -> Self {
         Self {
@@ -48,99 +330,307 @@ impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> LcdPins<RS, RW, E,
             read_write,
             enable,
             data_bus,
+            controller: ControllerState::default(),
         }
     }
 
+    /// Measures execution times against `clock` instead of spending them with the delay,
+    /// see [`ReadWritePin::READABLE`].
+    #[inline]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.controller.countdown.set_clock(Some(clock));
+        self
+    }
+
     pub(crate) fn pulse_enable(&mut self, delay: &mut impl DelayMicros) -> Result<(), E::Error> {
+        self.raise_enable(delay)?;
+        self.lower_enable(delay)
+    }
+
+    /// Raises E once RS and RW have settled, then waits out the minimum pulse width.
+    fn raise_enable(&mut self, delay: &mut impl DelayMicros) -> Result<(), E::Error> {
+        delay.delay_ns(ADDRESS_SETUP_NS);
         self.enable.set_high()?;
         delay.delay_us(1);
+        Ok(())
+    }
+
+    /// Lowers E, then waits out the hold times and the rest of the enable cycle.
+    fn lower_enable(&mut self, delay: &mut impl DelayMicros) -> Result<(), E::Error> {
         self.enable.set_low()?;
         delay.delay_us(1);
         Ok(())
     }
 
-    pub fn state(
+    fn read_state(
         &mut self,
         delay: &mut impl DelayMicros,
     ) -> Result<State, LcdError<RS, RW, E, DB>> {
+        self.begin_read()?;
+        let mut bits = bitarr!(u8, Lsb0; 0; 8);
+        match DB::DATA_LENGTH {
+            DataLength::Eight => self.read_bits(delay, &mut bits[..])?,
+            DataLength::Four => {
+                self.read_bits(delay, &mut bits[4..])?;
+                self.read_bits(delay, &mut bits[..4])?;
+            }
+        }
+        self.end_read()?;
+        Ok(State(bits.load::<u8>()))
+    }
+
+    /// Selects the instruction register and hands the data lines over to the controller.
+    pub(crate) fn begin_read(&mut self) -> Result<(), LcdError<RS, RW, E, DB>> {
         self.register_selection
             .set_low()
             .map_err(|e| LcdError::RegisterSelectionError(e))?;
+        self.data_bus
+            .set_input_mode()
+            .map_err(|e| LcdError::DataBusError(e))?;
+        self.read_write
+            .set_read()
+            .map_err(|e| LcdError::ReadWriteError(e))
+    }
+
+    pub(crate) fn end_read(&mut self) -> Result<(), LcdError<RS, RW, E, DB>> {
         self.read_write
-            .set_high()
+            .set_write()
             .map_err(|e| LcdError::ReadWriteError(e))?;
+        self.data_bus
+            .set_output_mode()
+            .map_err(|e| LcdError::DataBusError(e))
+    }
+
+    /// Sets RS for `deliverable` and RW for writing, returning the byte to transfer.
+    pub(crate) fn begin_write(
+        &mut self,
+        deliverable: Deliverable,
+    ) -> Result<u8, LcdError<RS, RW, E, DB>> {
+        let datum = match deliverable {
+            Deliverable::Instr(CompiledInstr(datum)) => {
+                self.register_selection
+                    .set_low()
+                    .map_err(|e| LcdError::RegisterSelectionError(e))?;
+                datum
+            }
+            Deliverable::Data(datum) => {
+                self.register_selection
+                    .set_high()
+                    .map_err(|e| LcdError::RegisterSelectionError(e))?;
+                datum
+            }
+        };
+        self.read_write
+            .set_write()
+            .map_err(|e| LcdError::ReadWriteError(e))?;
+        Ok(datum)
+    }
+
+    /// The `i`th transfer of `phase` with the controller it goes to, or `None` once they have
+    /// all been made.
+    pub(crate) fn transfer(&self, phase: Phase, i: u8) -> Option<(Option<u8>, Deliverable)> {
+        match phase {
+            Phase::DisplayControl if i < E::CONTROLLERS => {
+                let ControllerState {
+                    selected,
+                    display_control,
+                    ..
+                } = self.controller;
+                let datum = match i == selected {
+                    true => display_control,
+                    false => display_control & !0x03,
+                };
+                Some((Some(i), Deliverable::Instr(CompiledInstr(datum))))
+            }
+            Phase::Transfer(target, deliverable) if i == 0 => Some((target, deliverable)),
+            _ => None,
+        }
+    }
+
+    /// Records that every transfer of `phase` has been made, and starts waiting out its
+    /// execution time when the busy flag cannot be read.
+    pub(crate) fn complete(&mut self, phase: Phase) {
+        let execution_time_us = match phase {
+            Phase::DisplayControl => {
+                self.controller.cursor_on = self.controller.selected;
+                DisplayControl::EXECUTION_TIME_US
+            }
+            Phase::Transfer(_, deliverable) => deliverable.execution_time_us(),
+        };
+        if !RW::READABLE {
+            self.controller.countdown.start(execution_time_us);
+        }
+    }
+
+    /// The controllers `target` stands for, every one for `None`.
+    pub(crate) fn controllers(target: Option<u8>) -> impl Iterator<Item = u8> {
+        (0..E::CONTROLLERS)
+            .filter(move |&controller| target.is_none_or(|target| target == controller))
+    }
+
+    /// Transfers `deliverable` to the controllers chosen through [`EnablePin::select`].
+    fn write_datum(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        let datum = self.begin_write(deliverable)?;
+        match DB::DATA_LENGTH {
+            DataLength::Eight => self.write_bits(delay, datum.view_bits::<Lsb0>()),
+            DataLength::Four => {
+                let (lower_bits, upper_bits) = datum.view_bits::<Lsb0>().split_at(4);
+                self.write_bits(delay, upper_bits)?;
+                self.write_bits(delay, lower_bits)
+            }
+        }
+    }
+
+    /// Returns [`nb::Error::WouldBlock`] while the `target` controllers are still executing
+    /// the previous transfer, judged by their busy flags or, without an RW line, by its
+    /// execution time.
+    fn poll_ready(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        target: Option<u8>,
+    ) -> nb::Result<(), LcdError<RS, RW, E, DB>> {
+        if RW::READABLE {
+            for controller in Self::controllers(target) {
+                self.enable.select(Some(controller));
+                if self.read_state(delay)?.busy() {
+                    return Err(nb::Error::WouldBlock);
+                }
+            }
+            Ok(())
+        } else {
+            self.controller
+                .countdown
+                .poll(delay)
+                .map_err(|_| nb::Error::WouldBlock)
+        }
+    }
+
+    fn write_bits(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        bits: &BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.put_bits(bits)?;
         self.pulse_enable(delay)
+            .map_err(|e| LcdError::EnableError(e))
+    }
+
+    fn read_bits(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        bits: &mut BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        // The controller only drives the data lines while E is high.
+        self.raise_enable(delay)
             .map_err(|e| LcdError::EnableError(e))?;
-        let lower_bits = self
-            .data_bus
-            .read_pins_now()
-            .map_err(|e| LcdError::DataBusError(e))?;
-        self.pulse_enable(delay)
-            .map_err(|e| LcdError::EnableError(e))?;
-        let upper_bits = self
-            .data_bus
-            .read_pins_now()
-            .map_err(|e| LcdError::DataBusError(e))?;
-        let mut bits = bitarr!(u8, Lsb0; 0; 8);
+        self.take_bits(bits)?;
+        self.lower_enable(delay)
+            .map_err(|e| LcdError::EnableError(e))
+    }
+
+    /// Drives the data lines to `bits` without pulsing E.
+    pub(crate) fn put_bits(
+        &mut self,
+        bits: &BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.data_bus
+            .write_pins_now(bits.iter().map(|b| match b.as_ref() {
+                false => PinState::Low,
+                true => PinState::High,
+            }))
+            .map_err(|e| LcdError::DataBusError(e))
+    }
+
+    /// Samples the data lines into `bits` without pulsing E.
+    pub(crate) fn take_bits(
+        &mut self,
+        bits: &mut BitSlice<u8, Lsb0>,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
         bits.iter_mut()
-            .zip(lower_bits.into_iter().chain(upper_bits.into_iter()))
+            .zip(
+                self.data_bus
+                    .read_pins_now()
+                    .map_err(|e| LcdError::DataBusError(e))?,
+            )
             .for_each(|(mut b, state)| {
                 b.set(match state {
                     PinState::Low => false,
                     PinState::High => true,
                 })
             });
-        Ok(State(bits.load::<u8>()))
+        Ok(())
+    }
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> Interface
+    for LcdPins<RS, RW, E, DB>
+{
+    type Error = LcdError<RS, RW, E, DB>;
+
+    const DATA_LENGTH: DataLength = DB::DATA_LENGTH;
+
+    fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        nibble: u8,
+    ) -> Result<(), LcdError<RS, RW, E, DB>> {
+        self.enable.select(None);
+        let datum = self.begin_write(Deliverable::Instr(CompiledInstr(nibble << 4)))?;
+        match DB::DATA_LENGTH {
+            DataLength::Eight => self.write_bits(delay, datum.view_bits::<Lsb0>()),
+            DataLength::Four => self.write_bits(delay, &datum.view_bits::<Lsb0>()[4..]),
+        }
     }
 
-    pub fn write(
+    fn write(
         &mut self,
         delay: &mut impl DelayMicros,
         deliverable: Deliverable,
     ) -> nb::Result<(), LcdError<RS, RW, E, DB>> {
-        if self.state(delay)?.busy() {
-            Err(nb::Error::WouldBlock)
-        } else {
-            let datum = match deliverable {
-                Deliverable::Instr(CompiledInstr(datum)) => {
-                    self.register_selection
-                        .set_low()
-                        .map_err(|e| LcdError::RegisterSelectionError(e))?;
-                    datum
-                }
-                Deliverable::Data(datum) => {
-                    self.register_selection
-                        .set_high()
-                        .map_err(|e| LcdError::RegisterSelectionError(e))?;
-                    datum
-                }
-            };
-            self.read_write
-                .set_low()
-                .map_err(|e| LcdError::ReadWriteError(e))?;
-            let (lower_bits, upper_bits) = datum.view_bits::<Lsb0>().split_at(4);
-            self.data_bus
-                .write_pins_now(lower_bits.iter().map(|b| match b.as_ref() {
-                    false => PinState::Low,
-                    true => PinState::High,
-                }))
-                .map_err(|e| LcdError::DataBusError(e))?;
-            self.pulse_enable(delay)
-                .map_err(|e| LcdError::EnableError(e))?;
-            self.data_bus
-                .write_pins_now(upper_bits.iter().map(|b| match b.as_ref() {
-                    false => PinState::Low,
-                    true => PinState::High,
-                }))
-                .map_err(|e| LcdError::DataBusError(e))?;
-            self.pulse_enable(delay)
-                .map_err(|e| LcdError::EnableError(e))?;
-            Ok(())
+        for phase in self.controller.plan(deliverable).into_iter().flatten() {
+            self.poll_ready(delay, phase.target())?;
+            let mut i = 0;
+            while let Some((controller, datum)) = self.transfer(phase, i) {
+                self.enable.select(controller);
+                self.write_datum(delay, datum)?;
+                i += 1;
+            }
+            self.complete(phase);
         }
+        Ok(())
+    }
+
+    #[inline]
+    fn select(&mut self, controller: u8) {
+        self.controller.select(controller, E::CONTROLLERS);
+    }
+
+    #[inline]
+    fn selected(&self) -> u8 {
+        self.controller.selected
+    }
+
+    #[inline]
+    fn clock(&self) -> Option<Clock> {
+        self.controller.countdown.clock()
+    }
+}
+
+impl<RS: OutputPin, RW: OutputPin, E: EnablePin, DB: DataBus> ReadInterface
+    for LcdPins<RS, RW, E, DB>
+{
+    #[inline]
+    fn state(&mut self, delay: &mut impl DelayMicros) -> Result<State, Self::Error> {
+        self.enable.select(Some(self.controller.selected));
+        self.read_state(delay)
     }
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> From<LcdPins<RS, RW, E, DB>>
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> From<LcdPins<RS, RW, E, DB>>
     for (RS, RW, E, DB)
 {
     #[inline]
@@ -154,50 +644,119 @@ impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> From<LcdPins<RS, R
     }
 }
 
-pub struct Lcd<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus, D: DelayMicros> {
-    pub(crate) pins: LcdPins<RS, RW, E, DB>,
+pub struct Lcd<I: Interface, D: DelayMicros> {
+    pub(crate) interface: I,
     pub(crate) delay: D,
+    geometry: Geometry,
+    charset: Charset,
+    pub(crate) font: Font,
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus> LcdPins<RS, RW, E, DB> {
+impl<RS: OutputPin, RW: ReadWritePin, E: EnablePin, DB: DataBus> LcdPins<RS, RW, E, DB> {
     #[inline]
-    pub fn with_delay<D: DelayMicros>(self, delay: D) -> Lcd<RS, RW, E, DB, D> {
-        Lcd { pins: self, delay }
+    pub fn with_delay<D: DelayMicros>(self, delay: D) -> Lcd<Self, D> {
+        Lcd::new(self, delay)
     }
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus, D: DelayMicros>
-    From<Lcd<RS, RW, E, DB, D>> for (LcdPins<RS, RW, E, DB>, D)
-{
-    fn from(value: Lcd<RS, RW, E, DB, D>) -> Self {
-        (value.pins, value.delay)
+impl<I: Interface, D: DelayMicros> From<Lcd<I, D>> for (I, D) {
+    fn from(value: Lcd<I, D>) -> Self {
+        (value.interface, value.delay)
     }
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus, D: DelayMicros>
-    Lcd<RS, RW, E, DB, D>
-{
-    pub fn state(&mut self) -> Result<State, LcdError<RS, RW, E, DB>> {
-        self.pins.state(&mut self.delay)
+impl<I: Interface, D: DelayMicros> Lcd<I, D> {
+    #[inline]
+    pub fn new(interface: I, delay: D) -> Self {
+        Self {
+            interface,
+            delay,
+            geometry: Geometry::default(),
+            charset: Charset::default(),
+            font: Font::Dots5x8,
+        }
     }
 
-    pub fn write(&mut self, deliverable: Deliverable) -> nb::Result<(), LcdError<RS, RW, E, DB>> {
-        self.pins.write(&mut self.delay, deliverable)
+    /// Sets the layout used for cursor positions, 16x2 by default.
+    #[inline]
+    pub fn with_geometry(self, geometry: Geometry) -> Self {
+        Self { geometry, ..self }
+    }
+
+    #[inline]
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Sets how text is translated for the character ROM, [`Rom::A00`](charset::Rom::A00)
+    /// by default.
+    #[inline]
+    pub fn with_charset(self, charset: Charset) -> Self {
+        Self { charset, ..self }
+    }
+
+    #[inline]
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    /// The interface, for what it offers besides the display, such as the backlight of
+    /// [`Pcf8574`](pcf8574::Pcf8574) or the extra pins of [`Mcp230xx`](mcp230xx::Mcp230xx).
+    #[inline]
+    pub fn interface_mut(&mut self) -> &mut I {
+        &mut self.interface
+    }
+
+    pub fn write(&mut self, deliverable: Deliverable) -> nb::Result<(), I::Error> {
+        self.interface.write(&mut self.delay, deliverable)
+    }
+
+    /// Moves the cursor to a position of the [`Geometry`]. Positions past the last row or
+    /// column wrap around.
+    pub fn set_cursor(&mut self, row: u8, col: u8) -> nb::Result<(), I::Error> {
+        let geometry = self.geometry;
+        let row = row % geometry.rows();
+        self.interface.select(geometry.controller(row));
+        let addr = geometry
+            .addr(row, col % geometry.cols())
+            .unwrap_or(SetDdramAddr::new_masked(0));
+        self.write(addr.compile().into())
+    }
+
+    /// Starts the power-on initialization sequence, see [`LcdInit`](crate::init::LcdInit).
+    #[inline]
+    pub fn init(
+        self,
+        function_set: FunctionSet,
+        entry_mode: EntryModeSet,
+    ) -> crate::init::LcdInit<I, D> {
+        crate::init::LcdInit::new(self, function_set, entry_mode)
     }
 }
 
-impl<RS: OutputPin, RW: OutputPin, E: OutputPin, DB: DataBus, D: DelayMicros> uWrite
-    for Lcd<RS, RW, E, DB, D>
-{
-    type Error = nb::Error<LcdError<RS, RW, E, DB>>;
+impl<I: ReadInterface, D: DelayMicros> Lcd<I, D> {
+    pub fn state(&mut self) -> Result<State, I::Error> {
+        self.interface.state(&mut self.delay)
+    }
+
+    /// The position of the cursor, or `None` while it is outside the visible area.
+    pub fn cursor(&mut self) -> Result<Option<(u8, u8)>, I::Error> {
+        let addr = self.state()?.addr();
+        Ok(self.geometry.position_on(self.interface.selected(), addr))
+    }
+}
+
+impl<I: Interface, D: DelayMicros> uWrite for Lcd<I, D> {
+    type Error = nb::Error<I::Error>;
 
     fn write_char(&mut self, c: char) -> Result<(), Self::Error> {
-        self.write(Deliverable::Data(c as u8))
+        self.charset
+            .codes(c)
+            .try_for_each(|code| nb::block!(self.write(Deliverable::Data(code))))?;
+        Ok(())
     }
 
     fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
-        s.bytes()
-            .try_for_each(|b| nb::block!(self.write(Deliverable::Data(b))))?;
-        Ok(())
+        s.chars().try_for_each(|c| self.write_char(c))
     }
 }
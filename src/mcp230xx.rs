@@ -0,0 +1,333 @@
+use hal::i2c::I2c;
+
+use crate::instr::*;
+use crate::utils::{self, Clock, Countdown, DelayMicros, State};
+use crate::{hal, Interface, ReadInterface};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Variant {
+    /// 8 lines, GP0–GP7 as pins 0–7.
+    Mcp23008,
+    /// 16 lines, GPA0–GPA7 as pins 0–7 and GPB0–GPB7 as pins 8–15.
+    Mcp23017,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Register {
+    Iodir,
+    Gppu,
+    Gpio,
+    Olat,
+}
+
+impl Variant {
+    /// Register addresses with the power-on IOCON.BANK = 0 layout.
+    const fn address(self, register: Register) -> u8 {
+        match (self, register) {
+            (Self::Mcp23008, Register::Iodir) => 0x00,
+            (Self::Mcp23008, Register::Gppu) => 0x06,
+            (Self::Mcp23008, Register::Gpio) => 0x09,
+            (Self::Mcp23008, Register::Olat) => 0x0A,
+            (Self::Mcp23017, Register::Iodir) => 0x00,
+            (Self::Mcp23017, Register::Gppu) => 0x0C,
+            (Self::Mcp23017, Register::Gpio) => 0x12,
+            (Self::Mcp23017, Register::Olat) => 0x14,
+        }
+    }
+}
+
+/// Assignment of the expander pins to the controller, given as pin numbers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Mcp230xxMapping {
+    pub register_selection: u8,
+    /// `None` when RW is tied to ground, in which case transfers are timed instead.
+    pub read_write: Option<u8>,
+    pub enable: u8,
+    /// Pins wired to DB4–DB7, in that order.
+    pub data: [u8; 4],
+}
+
+impl Mcp230xxMapping {
+    /// The Adafruit I2C/SPI character LCD backpack (MCP23008), whose backlight is on GP7.
+    pub const ADAFRUIT_BACKPACK: Self = Self {
+        register_selection: 1,
+        read_write: None,
+        enable: 2,
+        data: [3, 4, 5, 6],
+    };
+
+    /// The Adafruit RGB LCD shield (MCP23017), see [`rgb_shield`] for its other pins.
+    pub const ADAFRUIT_RGB_SHIELD: Self = Self {
+        register_selection: 15,
+        read_write: Some(14),
+        enable: 13,
+        data: [12, 11, 10, 9],
+    };
+
+    const fn data_mask(&self, nibble: u8) -> u16 {
+        utils::data_mask(&self.data, nibble)
+    }
+
+    const fn nibble(&self, port: u16) -> u8 {
+        utils::nibble(&self.data, port)
+    }
+
+    const fn read_write_mask(&self) -> u16 {
+        match self.read_write {
+            Some(pin) => 1 << pin,
+            None => 0,
+        }
+    }
+
+    const fn lcd_mask(&self) -> u16 {
+        1 << self.register_selection
+            | self.read_write_mask()
+            | 1 << self.enable
+            | self.data_mask(0x0F)
+    }
+}
+
+/// Pins of the Adafruit RGB LCD shield besides the display.
+pub mod rgb_shield {
+    /// Backlight LEDs, lit while their pin is low.
+    pub const RED: u16 = 1 << 6;
+    pub const GREEN: u16 = 1 << 7;
+    pub const BLUE: u16 = 1 << 8;
+    pub const BACKLIGHT: u16 = RED | GREEN | BLUE;
+
+    /// Buttons, pulling their pin low while pressed.
+    pub const SELECT: u16 = 1 << 0;
+    pub const RIGHT: u16 = 1 << 1;
+    pub const DOWN: u16 = 1 << 2;
+    pub const UP: u16 = 1 << 3;
+    pub const LEFT: u16 = 1 << 4;
+    pub const BUTTONS: u16 = SELECT | RIGHT | DOWN | UP | LEFT;
+}
+
+/// An [`Interface`] over an MCP23008 or MCP23017 I2C expander.
+///
+/// When RW is wired, data lines are switched to inputs through IODIR to read the busy flag.
+/// Expander pins not used by the display stay available through the `extra` methods.
+pub struct Mcp230xx<I2C> {
+    i2c: I2C,
+    address: u8,
+    variant: Variant,
+    mapping: Mcp230xxMapping,
+    configured: bool,
+    iodir: u16,
+    gppu: u16,
+    olat: u16,
+    countdown: Countdown,
+}
+
+#[derive(Debug)]
+pub enum Mcp230xxError<E> {
+    I2cError(E),
+    /// The busy flag was requested but the mapping has no RW line.
+    NotReadable,
+}
+
+impl<E> From<E> for Mcp230xxError<E> {
+    #[inline]
+    fn from(value: E) -> Self {
+        Self::I2cError(value)
+    }
+}
+
+impl<I2C: I2c> Mcp230xx<I2C> {
+    /// Address with A0–A2 pulled down.
+    pub const DEFAULT_ADDRESS: u8 = 0x20;
+
+    #[inline]
+    pub fn new(i2c: I2C, address: u8, variant: Variant, mapping: Mcp230xxMapping) -> Self {
+        Self {
+            i2c,
+            address,
+            variant,
+            mapping,
+            configured: false,
+            iodir: 0xFFFF,
+            gppu: 0x0000,
+            olat: 0x0000,
+            countdown: Countdown::default(),
+        }
+    }
+
+    /// Measures execution times against `clock` instead of spending them with the delay, when
+    /// the mapping has no RW line.
+    #[inline]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.countdown.set_clock(Some(clock));
+        self
+    }
+
+    #[inline]
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Makes the `inputs` pins inputs, with pull-ups on the `pull_ups` pins, and every other
+    /// pin not used by the display an output.
+    pub fn set_extra_direction(&mut self, inputs: u16, pull_ups: u16) -> Result<(), I2C::Error> {
+        let extra = !self.mapping.lcd_mask();
+        self.iodir = (self.iodir & !extra) | (inputs & extra);
+        self.gppu = (self.gppu & !extra) | (pull_ups & extra);
+        self.write_register(Register::Gppu, self.gppu)?;
+        self.write_register(Register::Iodir, self.iodir)
+    }
+
+    /// Drives the `mask` output pins not used by the display to `values`.
+    pub fn write_extra(&mut self, mask: u16, values: u16) -> Result<(), I2C::Error> {
+        let mask = mask & !self.mapping.lcd_mask();
+        self.olat = (self.olat & !mask) | (values & mask);
+        self.write_register(Register::Olat, self.olat)
+    }
+
+    /// Reads the level of every pin not used by the display.
+    pub fn read_extra(&mut self) -> Result<u16, I2C::Error> {
+        Ok(self.read_register(Register::Gpio)? & !self.mapping.lcd_mask())
+    }
+
+    fn write_register(&mut self, register: Register, value: u16) -> Result<(), I2C::Error> {
+        let address = self.variant.address(register);
+        let [low, high] = value.to_le_bytes();
+        match self.variant {
+            Variant::Mcp23008 => self.i2c.write(self.address, &[address, low]),
+            Variant::Mcp23017 => self.i2c.write(self.address, &[address, low, high]),
+        }
+    }
+
+    fn read_register(&mut self, register: Register) -> Result<u16, I2C::Error> {
+        let address = self.variant.address(register);
+        let mut buffer = [0; 2];
+        let len = match self.variant {
+            Variant::Mcp23008 => 1,
+            Variant::Mcp23017 => 2,
+        };
+        self.i2c
+            .write_read(self.address, &[address], &mut buffer[..len])?;
+        Ok(u16::from_le_bytes(buffer))
+    }
+
+    /// Turns the display pins into outputs the first time the display is accessed.
+    fn configure(&mut self) -> Result<(), I2C::Error> {
+        if !self.configured {
+            self.olat &= !self.mapping.lcd_mask();
+            self.write_register(Register::Olat, self.olat)?;
+            self.iodir &= !self.mapping.lcd_mask();
+            self.write_register(Register::Iodir, self.iodir)?;
+            self.configured = true;
+        }
+        Ok(())
+    }
+
+    fn set_lines(&mut self, mask: u16, values: u16) -> Result<(), I2C::Error> {
+        self.olat = (self.olat & !mask) | (values & mask);
+        self.write_register(Register::Olat, self.olat)
+    }
+
+    fn strobe(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        register_selection: bool,
+        nibble: u8,
+    ) -> Result<(), I2C::Error> {
+        let mapping = self.mapping;
+        let enable = 1 << mapping.enable;
+        let mask = 1 << mapping.register_selection | mapping.read_write_mask() | enable;
+        let values = (register_selection as u16) << mapping.register_selection;
+        // RS, RW and the data lines settle with E low before the pulse.
+        self.set_lines(
+            mask | mapping.data_mask(0x0F),
+            values | mapping.data_mask(nibble),
+        )?;
+        self.set_lines(enable, enable)?;
+        delay.delay_us(1);
+        self.set_lines(enable, 0)?;
+        delay.delay_us(1);
+        Ok(())
+    }
+
+    fn read_nibble(&mut self, delay: &mut impl DelayMicros) -> Result<u8, I2C::Error> {
+        let enable = 1 << self.mapping.enable;
+        self.set_lines(enable, enable)?;
+        delay.delay_us(1);
+        let port = self.read_register(Register::Gpio)?;
+        self.set_lines(enable, 0)?;
+        delay.delay_us(1);
+        Ok(self.mapping.nibble(port))
+    }
+}
+
+impl<I2C: I2c> Interface for Mcp230xx<I2C> {
+    type Error = Mcp230xxError<I2C::Error>;
+
+    const DATA_LENGTH: DataLength = DataLength::Four;
+
+    fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        nibble: u8,
+    ) -> Result<(), Self::Error> {
+        self.configure()?;
+        Ok(self.strobe(delay, false, nibble)?)
+    }
+
+    fn write(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> nb::Result<(), Self::Error> {
+        self.configure().map_err(Mcp230xxError::I2cError)?;
+        if self.mapping.read_write.is_some() {
+            if self.state(delay)?.busy() {
+                return Err(nb::Error::WouldBlock);
+            }
+        } else {
+            self.countdown
+                .poll(delay)
+                .map_err(|_| nb::Error::WouldBlock)?;
+        }
+        let (register_selection, datum) = match deliverable {
+            Deliverable::Instr(CompiledInstr(datum)) => (false, datum),
+            Deliverable::Data(datum) => (true, datum),
+        };
+        self.strobe(delay, register_selection, datum >> 4)
+            .map_err(Mcp230xxError::I2cError)?;
+        self.strobe(delay, register_selection, datum & 0x0F)
+            .map_err(Mcp230xxError::I2cError)?;
+        if self.mapping.read_write.is_none() {
+            self.countdown.start(deliverable.execution_time_us());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn clock(&self) -> Option<Clock> {
+        self.countdown.clock()
+    }
+}
+
+impl<I2C: I2c> ReadInterface for Mcp230xx<I2C> {
+    /// Fails with [`Mcp230xxError::NotReadable`] if the mapping has no RW line.
+    fn state(&mut self, delay: &mut impl DelayMicros) -> Result<State, Self::Error> {
+        let read_write = self.mapping.read_write_mask();
+        if read_write == 0 {
+            return Err(Mcp230xxError::NotReadable);
+        }
+        self.configure()?;
+        let data = self.mapping.data_mask(0x0F);
+        self.iodir |= data;
+        self.write_register(Register::Iodir, self.iodir)?;
+        self.set_lines(
+            1 << self.mapping.register_selection | read_write,
+            read_write,
+        )?;
+        let upper = self.read_nibble(delay)?;
+        let lower = self.read_nibble(delay)?;
+        self.set_lines(read_write, 0)?;
+        self.iodir &= !data;
+        self.write_register(Register::Iodir, self.iodir)?;
+        Ok(State(upper << 4 | lower))
+    }
+}
@@ -0,0 +1,169 @@
+use hal::i2c::I2c;
+
+use crate::instr::*;
+use crate::utils::{self, DelayMicros, State};
+use crate::{hal, Interface, ReadInterface};
+
+/// Assignment of the expander lines P0–P7 to the controller, given as line numbers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Pcf8574Mapping {
+    pub register_selection: u8,
+    pub read_write: u8,
+    pub enable: u8,
+    pub backlight: u8,
+    /// Lines wired to DB4–DB7, in that order.
+    pub data: [u8; 4],
+    /// Whether the backlight turns on when its line is low.
+    pub backlight_active_low: bool,
+}
+
+impl Pcf8574Mapping {
+    /// RS, RW, E and backlight on P0–P3 and DB4–DB7 on P4–P7, as on most backpacks.
+    pub const COMMON: Self = Self {
+        register_selection: 0,
+        read_write: 1,
+        enable: 2,
+        backlight: 3,
+        data: [4, 5, 6, 7],
+        backlight_active_low: false,
+    };
+
+    /// DB4–DB7 on P0–P3, E, RW and RS on P4–P6 and an inverted backlight on P7, as on mjkdz
+    /// backpacks.
+    pub const MJKDZ: Self = Self {
+        register_selection: 6,
+        read_write: 5,
+        enable: 4,
+        backlight: 7,
+        data: [0, 1, 2, 3],
+        backlight_active_low: true,
+    };
+
+    const fn data_mask(&self, nibble: u8) -> u8 {
+        utils::data_mask(&self.data, nibble) as u8
+    }
+
+    const fn nibble(&self, port: u8) -> u8 {
+        utils::nibble(&self.data, port as u16)
+    }
+}
+
+impl Default for Pcf8574Mapping {
+    #[inline]
+    fn default() -> Self {
+        Self::COMMON
+    }
+}
+
+/// An [`Interface`] over a PCF8574 or PCF8574A I2C backpack, which drives RS, RW, E, the
+/// backlight and DB4–DB7 from a single expander port.
+///
+/// The busy flag is read through the quasi-bidirectional port by setting the data lines high
+/// and letting the controller pull them down.
+pub struct Pcf8574<I2C> {
+    i2c: I2C,
+    address: u8,
+    mapping: Pcf8574Mapping,
+    backlight: bool,
+}
+
+impl<I2C: I2c> Pcf8574<I2C> {
+    /// Address of a PCF8574 with A0–A2 pulled up, as shipped on most backpacks.
+    pub const PCF8574_ADDRESS: u8 = 0x27;
+    /// Address of a PCF8574A with A0–A2 pulled up, as shipped on most backpacks.
+    pub const PCF8574A_ADDRESS: u8 = 0x3F;
+
+    #[inline]
+    pub fn new(i2c: I2C, address: u8, mapping: Pcf8574Mapping) -> Self {
+        Self {
+            i2c,
+            address,
+            mapping,
+            backlight: true,
+        }
+    }
+
+    #[inline]
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    #[inline]
+    pub fn backlight(&self) -> bool {
+        self.backlight
+    }
+
+    pub fn set_backlight(&mut self, on: bool) -> Result<(), I2C::Error> {
+        self.backlight = on;
+        let port = self.port(false, false);
+        self.i2c.write(self.address, &[port])
+    }
+
+    fn port(&self, register_selection: bool, read: bool) -> u8 {
+        let mapping = &self.mapping;
+        (register_selection as u8) << mapping.register_selection
+            | (read as u8) << mapping.read_write
+            | ((self.backlight != mapping.backlight_active_low) as u8) << mapping.backlight
+    }
+
+    fn write_byte(&mut self, register_selection: bool, datum: u8) -> Result<(), I2C::Error> {
+        let port = self.port(register_selection, false);
+        let enable = 1 << self.mapping.enable;
+        let upper = port | self.mapping.data_mask(datum >> 4);
+        let lower = port | self.mapping.data_mask(datum & 0x0F);
+        // RS and the data lines settle with E low before every pulse.
+        self.i2c.write(
+            self.address,
+            &[upper, upper | enable, upper, lower, lower | enable, lower],
+        )
+    }
+
+    fn read_nibble(&mut self, register_selection: bool) -> Result<u8, I2C::Error> {
+        let port = self.port(register_selection, true) | self.mapping.data_mask(0x0F);
+        let enable = 1 << self.mapping.enable;
+        let mut buffer = [0];
+        self.i2c.write(self.address, &[port, port | enable])?;
+        self.i2c.read(self.address, &mut buffer)?;
+        self.i2c.write(self.address, &[port])?;
+        Ok(self.mapping.nibble(buffer[0]))
+    }
+}
+
+impl<I2C: I2c> Interface for Pcf8574<I2C> {
+    type Error = I2C::Error;
+
+    const DATA_LENGTH: DataLength = DataLength::Four;
+
+    fn write_nibble(
+        &mut self,
+        _delay: &mut impl DelayMicros,
+        nibble: u8,
+    ) -> Result<(), I2C::Error> {
+        let port = self.port(false, false) | self.mapping.data_mask(nibble);
+        let enable = 1 << self.mapping.enable;
+        self.i2c.write(self.address, &[port, port | enable, port])
+    }
+
+    fn write(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> nb::Result<(), I2C::Error> {
+        if self.state(delay)?.busy() {
+            return Err(nb::Error::WouldBlock);
+        }
+        match deliverable {
+            Deliverable::Instr(CompiledInstr(datum)) => self.write_byte(false, datum),
+            Deliverable::Data(datum) => self.write_byte(true, datum),
+        }?;
+        Ok(())
+    }
+}
+
+impl<I2C: I2c> ReadInterface for Pcf8574<I2C> {
+    fn state(&mut self, _delay: &mut impl DelayMicros) -> Result<State, I2C::Error> {
+        let upper = self.read_nibble(false)?;
+        let lower = self.read_nibble(false)?;
+        Ok(State(upper << 4 | lower))
+    }
+}
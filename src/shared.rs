@@ -0,0 +1,201 @@
+use core::cell::RefCell;
+
+use hal::digital::OutputPin;
+
+use crate::instr::{DataLength, Deliverable};
+use crate::utils::{Clock, DelayMicros, State};
+use crate::{
+    hal, ControllerState, DataBus, EnablePin, Interface, Lcd, LcdError, LcdPins, ReadInterface,
+    ReadWritePin,
+};
+
+/// The RS, RW and data lines shared by several displays that differ only by their E line.
+///
+/// Put behind a [`BusLock`] such as a [`RefCell`], then give every display a
+/// [`SharedLcdPins`] with its own E line.
+pub struct BusLines<RS: OutputPin, RW: ReadWritePin, DB: DataBus> {
+    /// Empty only while a transfer has taken the lines. The transfer holds the [`BusLock`] and
+    /// never reaches back into the bus, so no other one can find them missing unless it
+    /// panicked halfway and the panic was caught.
+    lines: Option<(RS, RW, DB)>,
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, DB: DataBus> BusLines<RS, RW, DB> {
+    #[inline]
+    pub fn new(register_selection: RS, read_write: RW, data_bus: DB) -> Self {
+        Self {
+            lines: Some((register_selection, read_write, data_bus)),
+        }
+    }
+
+    fn take(&mut self) -> (RS, RW, DB) {
+        self.lines
+            .take()
+            .expect("bus lines taken by an unfinished transfer")
+    }
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, DB: DataBus> From<BusLines<RS, RW, DB>> for (RS, RW, DB) {
+    #[inline]
+    fn from(mut value: BusLines<RS, RW, DB>) -> Self {
+        value.take()
+    }
+}
+
+/// Exclusive access to [`BusLines`], held for one whole transfer including the busy flag
+/// reads before it.
+///
+/// Implemented for [`RefCell`], which panics when displays are used from interrupt handlers
+/// that preempt each other. With the `critical-section` feature, it is also implemented for
+/// a `critical_section::Mutex<RefCell<_>>`, which shares the lines across those by holding a
+/// critical section for each whole transfer.
+pub trait BusLock {
+    type RS: OutputPin;
+    type RW: ReadWritePin;
+    type DB: DataBus;
+
+    fn lock<R>(&self, f: impl FnOnce(&mut BusLines<Self::RS, Self::RW, Self::DB>) -> R) -> R;
+}
+
+impl<RS: OutputPin, RW: ReadWritePin, DB: DataBus> BusLock for RefCell<BusLines<RS, RW, DB>> {
+    type RS = RS;
+    type RW = RW;
+    type DB = DB;
+
+    #[inline]
+    fn lock<R>(&self, f: impl FnOnce(&mut BusLines<RS, RW, DB>) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<RS: OutputPin, RW: ReadWritePin, DB: DataBus> BusLock
+    for critical_section::Mutex<RefCell<BusLines<RS, RW, DB>>>
+{
+    type RS = RS;
+    type RW = RW;
+    type DB = DB;
+
+    #[inline]
+    fn lock<R>(&self, f: impl FnOnce(&mut BusLines<RS, RW, DB>) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.borrow_ref_mut(cs)))
+    }
+}
+
+/// One display on shared [`BusLines`], driven through its own E line.
+///
+/// Each display keeps its own busy state. With RW wired, the busy flag of each controller is
+/// read through its own E line, so one display can be written to while another is still
+/// executing a slow instruction such as Clear. With RW grounded, the execution time of a
+/// display is measured against the clock given through [`SharedLcdPins::with_clock`], so that
+/// writing to the others in the meantime counts towards it. Without a clock, it is only
+/// waited out inside the calls made for that display, see [`ReadWritePin::READABLE`].
+pub struct SharedLcdPins<'a, B: BusLock, E: EnablePin> {
+    bus: &'a B,
+    /// Empty only while a transfer has taken the pin, which `&mut self` keeps from being seen
+    /// unless the transfer panicked halfway.
+    enable: Option<E>,
+    controller: ControllerState,
+}
+
+impl<'a, B: BusLock, E: EnablePin> SharedLcdPins<'a, B, E> {
+    #[inline]
+    pub fn new(bus: &'a B, enable: E) -> Self {
+        Self {
+            bus,
+            enable: Some(enable),
+            controller: ControllerState::default(),
+        }
+    }
+
+    /// Measures execution times against `clock` instead of spending them with the delay.
+    #[inline]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.controller.countdown.set_clock(Some(clock));
+        self
+    }
+
+    #[inline]
+    pub fn with_delay<D: DelayMicros>(self, delay: D) -> Lcd<Self, D> {
+        Lcd::new(self, delay)
+    }
+
+    /// Gives back the E line, leaving the shared lines to the other displays.
+    #[inline]
+    pub fn release(self) -> E {
+        self.enable
+            .expect("enable line taken by an unfinished transfer")
+    }
+
+    /// Runs `f` on [`LcdPins`] made of the locked lines and the state of this display.
+    fn transfer<R>(&mut self, f: impl FnOnce(&mut LcdPins<B::RS, B::RW, E, B::DB>) -> R) -> R {
+        let Self {
+            bus,
+            enable,
+            controller,
+        } = self;
+        bus.lock(|lines| {
+            let (register_selection, read_write, data_bus) = lines.take();
+            let mut pins = LcdPins {
+                register_selection,
+                read_write,
+                enable: enable
+                    .take()
+                    .expect("enable line taken by an unfinished transfer"),
+                data_bus,
+                controller: *controller,
+            };
+            let result = f(&mut pins);
+            lines.lines = Some((pins.register_selection, pins.read_write, pins.data_bus));
+            *enable = Some(pins.enable);
+            *controller = pins.controller;
+            result
+        })
+    }
+}
+
+impl<B: BusLock, E: EnablePin> Interface for SharedLcdPins<'_, B, E> {
+    type Error = LcdError<B::RS, B::RW, E, B::DB>;
+
+    const DATA_LENGTH: DataLength = B::DB::DATA_LENGTH;
+
+    fn write_nibble(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        nibble: u8,
+    ) -> Result<(), Self::Error> {
+        self.transfer(|pins| pins.write_nibble(delay, nibble))
+    }
+
+    fn write(
+        &mut self,
+        delay: &mut impl DelayMicros,
+        deliverable: Deliverable,
+    ) -> nb::Result<(), Self::Error> {
+        self.transfer(|pins| pins.write(delay, deliverable))
+    }
+
+    #[inline]
+    fn select(&mut self, controller: u8) {
+        self.controller.select(controller, E::CONTROLLERS);
+    }
+
+    #[inline]
+    fn selected(&self) -> u8 {
+        self.controller.selected
+    }
+
+    #[inline]
+    fn clock(&self) -> Option<Clock> {
+        self.controller.countdown.clock()
+    }
+}
+
+impl<B: BusLock, E: EnablePin> ReadInterface for SharedLcdPins<'_, B, E>
+where
+    B::RW: OutputPin,
+{
+    fn state(&mut self, delay: &mut impl DelayMicros) -> Result<State, Self::Error> {
+        self.transfer(|pins| pins.state(delay))
+    }
+}
@@ -0,0 +1,275 @@
+use ufmt::uWrite;
+
+use crate::charset::Encoded;
+use crate::glyph::{char_code, slot_of, Glyph, GlyphCache, GlyphCacheError};
+use crate::instr::*;
+use crate::utils::DelayMicros;
+use crate::{Interface, Lcd};
+
+/// Largest number of characters shown, by a 40x4 display made of two controllers.
+const MAX_CHARS: usize = 160;
+
+/// What happens to text reaching the end of a row.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LineOverflow {
+    /// Continues on the next row.
+    #[default]
+    Wrap,
+    /// Drops characters until the next `\n`.
+    Clip,
+}
+
+/// What happens to text moving past the last row.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ScreenOverflow {
+    /// Moves every row up by one and continues on an empty last row.
+    #[default]
+    Scroll,
+    /// Drops everything until [`TextWriter::clear`].
+    Truncate,
+}
+
+/// A [`uWrite`] front-end to [`Lcd`] that lays text out along its
+/// [`Geometry`](crate::geometry::Geometry) instead of streaming it into DDRAM, handling `\n`
+/// and `\r`.
+///
+/// The writer keeps a copy of the visible characters to redraw them when scrolling, and
+/// assumes the display is cleared and left in the default increment entry mode. Text is
+/// translated with the [`Charset`](crate::charset::Charset) of the [`Lcd`], and the writer
+/// takes over CGRAM when the charset synthesizes glyphs.
+pub struct TextWriter<I: Interface, D: DelayMicros> {
+    lcd: Lcd<I, D>,
+    line_overflow: LineOverflow,
+    screen_overflow: ScreenOverflow,
+    shadow: [u8; MAX_CHARS],
+    row: u8,
+    col: u8,
+    /// Selected controller and its address counter, if known.
+    addr: Option<(u8, u8)>,
+    glyphs: GlyphCache<char>,
+}
+
+impl<I: Interface, D: DelayMicros> From<TextWriter<I, D>> for Lcd<I, D> {
+    #[inline]
+    fn from(value: TextWriter<I, D>) -> Self {
+        value.lcd
+    }
+}
+
+impl<I: Interface, D: DelayMicros> TextWriter<I, D> {
+    pub fn new(lcd: Lcd<I, D>) -> Self {
+        Self {
+            lcd,
+            line_overflow: LineOverflow::default(),
+            screen_overflow: ScreenOverflow::default(),
+            shadow: [b' '; MAX_CHARS],
+            row: 0,
+            col: 0,
+            addr: None,
+            glyphs: GlyphCache::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_line_overflow(self, line_overflow: LineOverflow) -> Self {
+        Self {
+            line_overflow,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_screen_overflow(self, screen_overflow: ScreenOverflow) -> Self {
+        Self {
+            screen_overflow,
+            ..self
+        }
+    }
+
+    /// The position the next character goes to, with the row equal to the number of rows once
+    /// text has been truncated.
+    #[inline]
+    pub fn position(&self) -> (u8, u8) {
+        (self.row, self.col)
+    }
+
+    /// The interface of the [`Lcd`], see [`Lcd::interface_mut`]. Writing to the display
+    /// through it leaves the writer out of step with the screen.
+    #[inline]
+    pub fn interface_mut(&mut self) -> &mut I {
+        self.lcd.interface_mut()
+    }
+
+    /// Clears the display and moves back to the first row.
+    pub fn clear(&mut self) -> nb::Result<(), I::Error> {
+        nb::block!(self.lcd.write(Clear::compile().into()))?;
+        self.shadow.fill(b' ');
+        self.glyphs.forget_all();
+        self.row = 0;
+        self.col = 0;
+        self.addr = Some((0, 0));
+        Ok(())
+    }
+
+    /// Writes a single byte of the character ROM, or moves on `\n` and `\r`.
+    pub fn write_byte(&mut self, byte: u8) -> nb::Result<(), I::Error> {
+        match byte {
+            b'\n' => return self.new_line(),
+            b'\r' => {
+                self.col = 0;
+                return Ok(());
+            }
+            _ => {}
+        }
+        let Some((row, col)) = self.next_cell()? else {
+            return Ok(());
+        };
+        self.release(row, col);
+        self.put(row, col, byte)?;
+        self.store(row, col, byte);
+        Ok(())
+    }
+
+    /// Writes `glyph` through the glyph cache, or the fallback of the charset if every slot
+    /// is on screen.
+    fn write_glyph(&mut self, c: char, glyph: &Glyph) -> nb::Result<(), I::Error> {
+        let Some((row, col)) = self.next_cell()? else {
+            return Ok(());
+        };
+        let Some(addr) = self.lcd.geometry().addr(row, col) else {
+            return Ok(());
+        };
+        self.release(row, col);
+        let controller = self.lcd.geometry().controller(row);
+        self.lcd.interface.select(controller);
+        let code = match self.glyphs.print_at(&mut self.lcd, c, glyph, addr) {
+            Ok(()) => self
+                .glyphs
+                .slot(c)
+                .map(|slot| char_code(self.lcd.font, slot)),
+            Err(nb::Error::Other(GlyphCacheError::NoFreeSlot)) => None,
+            Err(nb::Error::Other(GlyphCacheError::InterfaceError(e))) => {
+                self.addr = None;
+                return Err(nb::Error::Other(e));
+            }
+            Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+        };
+        let code = match code {
+            Some(code) => {
+                self.addr = Some((controller, addr.addr() + 1));
+                code
+            }
+            None => {
+                let fallback = self.lcd.charset().fallback();
+                self.put(row, col, fallback)?;
+                fallback
+            }
+        };
+        self.store(row, col, code);
+        Ok(())
+    }
+
+    /// The position the next character goes to after wrapping or clipping, if any.
+    fn next_cell(&mut self) -> nb::Result<Option<(u8, u8)>, I::Error> {
+        let geometry = self.lcd.geometry();
+        if self.col >= geometry.cols() {
+            match self.line_overflow {
+                LineOverflow::Wrap => self.new_line()?,
+                LineOverflow::Clip => return Ok(None),
+            }
+        }
+        if self.row >= geometry.rows() {
+            return Ok(None);
+        }
+        Ok(Some((self.row, self.col)))
+    }
+
+    /// Records `byte` as shown at a position and moves past it.
+    fn store(&mut self, row: u8, col: u8, byte: u8) {
+        if let Some(cell) = self.cell(row, col) {
+            *cell = byte;
+        }
+        self.col = col + 1;
+    }
+
+    /// Tells the glyph cache that a glyph shown at a position is about to go.
+    fn release(&mut self, row: u8, col: u8) {
+        if let Some(&code) = self.cell(row, col).as_deref() {
+            if code < 8 {
+                self.glyphs.forget_slot(slot_of(self.lcd.font, code));
+            }
+        }
+    }
+
+    fn new_line(&mut self) -> nb::Result<(), I::Error> {
+        let rows = self.lcd.geometry().rows();
+        self.col = 0;
+        if self.row + 1 < rows {
+            self.row += 1;
+            return Ok(());
+        }
+        match self.screen_overflow {
+            ScreenOverflow::Scroll => self.scroll(),
+            ScreenOverflow::Truncate => {
+                self.row = rows;
+                Ok(())
+            }
+        }
+    }
+
+    fn scroll(&mut self) -> nb::Result<(), I::Error> {
+        let geometry = self.lcd.geometry();
+        let cols = geometry.cols() as usize;
+        let visible = (geometry.rows() as usize * cols).min(MAX_CHARS);
+        for col in 0..geometry.cols() {
+            self.release(0, col);
+        }
+        self.shadow.copy_within(cols..visible, 0);
+        self.shadow[visible.saturating_sub(cols)..visible].fill(b' ');
+        for row in 0..geometry.rows() {
+            for col in 0..geometry.cols() {
+                let byte = self.cell(row, col).map_or(b' ', |cell| *cell);
+                self.put(row, col, byte)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn cell(&mut self, row: u8, col: u8) -> Option<&mut u8> {
+        let cols = self.lcd.geometry().cols() as usize;
+        self.shadow.get_mut(row as usize * cols + col as usize)
+    }
+
+    /// Writes `byte` at a position, moving the cursor first unless it is already there.
+    fn put(&mut self, row: u8, col: u8, byte: u8) -> nb::Result<(), I::Error> {
+        let Some(addr) = self.lcd.geometry().addr(row, col) else {
+            return Ok(());
+        };
+        let controller = self.lcd.geometry().controller(row);
+        if self.addr != Some((controller, addr.addr())) {
+            self.lcd.interface.select(controller);
+            nb::block!(self.lcd.write(addr.compile().into()))?;
+        }
+        nb::block!(self.lcd.write(Deliverable::Data(byte)))?;
+        self.addr = Some((controller, addr.addr() + 1));
+        Ok(())
+    }
+}
+
+impl<I: Interface, D: DelayMicros> uWrite for TextWriter<I, D> {
+    type Error = nb::Error<I::Error>;
+
+    fn write_char(&mut self, c: char) -> Result<(), Self::Error> {
+        match c {
+            '\n' | '\r' => self.write_byte(c as u8),
+            _ => match self.lcd.charset().encode(c) {
+                Encoded::Codes(mut codes) => codes.try_for_each(|code| self.write_byte(code)),
+                Encoded::Glyph(glyph) => self.write_glyph(c, glyph),
+            },
+        }
+    }
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        s.chars().try_for_each(|c| self.write_char(c))
+    }
+}
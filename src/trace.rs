@@ -0,0 +1,309 @@
+use core::cell::RefCell;
+use core::fmt;
+
+use hal::delay::DelayNs;
+use hal::digital::{ErrorType, OutputPin, PinState};
+
+use crate::instr::DataLength;
+use crate::{hal, DataBus};
+
+/// A control line of the controller.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Line {
+    RegisterSelection,
+    ReadWrite,
+    Enable,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Change {
+    Line(Line, bool),
+    /// The data lines, lowest line in the lowest bit, or `None` while released to the
+    /// controller.
+    Data(Option<u8>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Event {
+    /// Time since the trace started, as told by the [`TracedDelay`].
+    pub at_ns: u64,
+    pub change: Change,
+}
+
+/// A record of up to `N` transitions on the lines of the controller, made by wrapping the
+/// pins given to [`LcdPins`](crate::LcdPins) in [`TracedPin`] and [`TracedBus`], and its delay
+/// in [`TracedDelay`] for the timestamps.
+///
+/// Only actual transitions are kept, and recording stops once the trace is full. The trace
+/// can be written out as a Value Change Dump with [`Trace::write_vcd`], to be viewed in a
+/// waveform viewer such as GTKWave.
+pub struct Trace<const N: usize> {
+    events: [Event; N],
+    len: usize,
+    dropped: usize,
+    now_ns: u64,
+    data_lines: u8,
+    lines: [Option<bool>; 3],
+    data: Option<Option<u8>>,
+}
+
+impl<const N: usize> Default for Trace<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Trace<N> {
+    pub const fn new() -> Self {
+        Self {
+            events: [Event {
+                at_ns: 0,
+                change: Change::Data(None),
+            }; N],
+            len: 0,
+            dropped: 0,
+            now_ns: 0,
+            data_lines: 4,
+            lines: [None; 3],
+            data: None,
+        }
+    }
+
+    #[inline]
+    pub fn events(&self) -> &[Event] {
+        &self.events[..self.len]
+    }
+
+    /// Number of transitions left out because the trace was full.
+    #[inline]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    #[inline]
+    pub fn now_ns(&self) -> u64 {
+        self.now_ns
+    }
+
+    /// Forgets every transition, keeping the clock running.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.dropped = 0;
+        self.lines = [None; 3];
+        self.data = None;
+    }
+
+    fn record(&mut self, change: Change) {
+        let changed = match change {
+            Change::Line(line, high) => self.lines[line as usize].replace(high) != Some(high),
+            Change::Data(data) => self.data.replace(data) != Some(data),
+        };
+        if !changed {
+            return;
+        }
+        match self.events.get_mut(self.len) {
+            Some(event) => {
+                *event = Event {
+                    at_ns: self.now_ns,
+                    change,
+                };
+                self.len += 1;
+            }
+            None => self.dropped += 1,
+        }
+    }
+
+    /// Writes the trace as a Value Change Dump with a 1 ns timescale.
+    pub fn write_vcd(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        out.write_str("$timescale 1ns $end\n$scope module lcd $end\n")?;
+        out.write_str("$var wire 1 ! rs $end\n$var wire 1 \" rw $end\n$var wire 1 # e $end\n")?;
+        writeln!(out, "$var wire {} $ db $end", self.data_lines)?;
+        out.write_str("$upscope $end\n$enddefinitions $end\n")?;
+        out.write_str("#0\n$dumpvars\nx!\nx\"\nx#\nbx $\n$end\n")?;
+        let mut at_ns = 0;
+        for event in self.events() {
+            if event.at_ns != at_ns {
+                at_ns = event.at_ns;
+                writeln!(out, "#{}", at_ns)?;
+            }
+            match event.change {
+                Change::Line(line, high) => {
+                    let id = match line {
+                        Line::RegisterSelection => '!',
+                        Line::ReadWrite => '"',
+                        Line::Enable => '#',
+                    };
+                    writeln!(out, "{}{}", high as u8, id)?;
+                }
+                Change::Data(Some(data)) => {
+                    writeln!(out, "b{:01$b} $", data, self.data_lines as usize)?;
+                }
+                Change::Data(None) => out.write_str("bz $\n")?,
+            }
+        }
+        if self.now_ns != at_ns {
+            writeln!(out, "#{}", self.now_ns)?;
+        }
+        Ok(())
+    }
+}
+
+/// An RS, RW or E pin recording its transitions in a [`Trace`].
+pub struct TracedPin<'a, P: OutputPin, const N: usize> {
+    pin: P,
+    trace: &'a RefCell<Trace<N>>,
+    line: Line,
+}
+
+impl<'a, P: OutputPin, const N: usize> TracedPin<'a, P, N> {
+    #[inline]
+    pub fn new(pin: P, trace: &'a RefCell<Trace<N>>, line: Line) -> Self {
+        Self { pin, trace, line }
+    }
+
+    #[inline]
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P: OutputPin, const N: usize> ErrorType for TracedPin<'_, P, N> {
+    type Error = P::Error;
+}
+
+impl<P: OutputPin, const N: usize> OutputPin for TracedPin<'_, P, N> {
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::Low)
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::High)
+    }
+
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        self.pin.set_state(state)?;
+        self.trace
+            .borrow_mut()
+            .record(Change::Line(self.line, state == PinState::High));
+        Ok(())
+    }
+}
+
+/// A [`DataBus`] recording what it writes and reads in a [`Trace`].
+pub struct TracedBus<'a, DB: DataBus, const N: usize> {
+    data_bus: DB,
+    trace: &'a RefCell<Trace<N>>,
+    /// Last value written, driven again when the lines go back to output.
+    written: Option<u8>,
+}
+
+impl<'a, DB: DataBus, const N: usize> TracedBus<'a, DB, N> {
+    #[inline]
+    pub fn new(data_bus: DB, trace: &'a RefCell<Trace<N>>) -> Self {
+        trace.borrow_mut().data_lines = match DB::DATA_LENGTH {
+            DataLength::Four => 4,
+            DataLength::Eight => 8,
+        };
+        Self {
+            data_bus,
+            trace,
+            written: None,
+        }
+    }
+
+    #[inline]
+    pub fn release(self) -> DB {
+        self.data_bus
+    }
+}
+
+fn pack(states: impl Iterator<Item = PinState>) -> u8 {
+    states
+        .enumerate()
+        .map(|(i, state)| ((state == PinState::High) as u8) << i)
+        .sum()
+}
+
+fn unpack(value: u8, len: usize) -> impl ExactSizeIterator<Item = PinState> {
+    (0..len).map(move |i| PinState::from(value & (1 << i) != 0))
+}
+
+impl<DB: DataBus, const N: usize> DataBus for TracedBus<'_, DB, N> {
+    type Error = DB::Error;
+
+    const DATA_LENGTH: DataLength = DB::DATA_LENGTH;
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        let len = states.len();
+        let value = pack(states);
+        self.data_bus.write_pins_now(unpack(value, len))?;
+        self.written = Some(value);
+        self.trace.borrow_mut().record(Change::Data(Some(value)));
+        Ok(())
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        let states = self.data_bus.read_pins_now()?;
+        let len = states.len();
+        let value = pack(states);
+        self.trace.borrow_mut().record(Change::Data(Some(value)));
+        Ok(unpack(value, len))
+    }
+
+    fn set_input_mode(&mut self) -> Result<(), Self::Error> {
+        self.data_bus.set_input_mode()?;
+        self.trace.borrow_mut().record(Change::Data(None));
+        Ok(())
+    }
+
+    fn set_output_mode(&mut self) -> Result<(), Self::Error> {
+        self.data_bus.set_output_mode()?;
+        self.trace.borrow_mut().record(Change::Data(self.written));
+        Ok(())
+    }
+}
+
+/// A delay moving the clock of a [`Trace`] on by the time asked for, so that timestamps
+/// follow the delays of the driver rather than a hardware timer.
+pub struct TracedDelay<'a, D: DelayNs, const N: usize> {
+    delay: D,
+    trace: &'a RefCell<Trace<N>>,
+}
+
+impl<'a, D: DelayNs, const N: usize> TracedDelay<'a, D, N> {
+    #[inline]
+    pub fn new(delay: D, trace: &'a RefCell<Trace<N>>) -> Self {
+        Self { delay, trace }
+    }
+
+    #[inline]
+    pub fn release(self) -> D {
+        self.delay
+    }
+}
+
+impl<D: DelayNs, const N: usize> DelayNs for TracedDelay<'_, D, N> {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay.delay_ns(ns);
+        self.trace.borrow_mut().now_ns += ns as u64;
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        self.delay.delay_us(us);
+        self.trace.borrow_mut().now_ns += us as u64 * 1_000;
+    }
+
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+        self.trace.borrow_mut().now_ns += ms as u64 * 1_000_000;
+    }
+}
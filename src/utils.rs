@@ -1,6 +1,7 @@
+use core::convert::Infallible;
 use core::fmt;
 
-use crate::hal::blocking::delay::DelayUs;
+use crate::hal::delay::DelayNs;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct State(pub(crate) u8);
@@ -28,6 +29,111 @@ impl fmt::Debug for State {
     }
 }
 
-pub trait DelayMicros: DelayUs<u8> {}
+pub trait DelayMicros: DelayNs {}
 
-impl<T: DelayUs<u8>> DelayMicros for T {}
+impl<T: DelayNs> DelayMicros for T {}
+
+/// Reads a free-running microsecond counter, such as a hardware timer, which may wrap around.
+pub type Clock = fn() -> u32;
+
+/// A wait measured against a [`Clock`] when one is given, so that time passing between polls
+/// counts towards it and [`Countdown::poll`] never blocks.
+///
+/// Without a clock, the wait is spent in bounded slices of [`DelayMicros`] instead, so that
+/// the caller can poll it without blocking for more than [`Countdown::SLICE_US`] at a time.
+/// Only the delays made by [`Countdown::poll`] then count.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Countdown {
+    remaining_us: u32,
+    clock: Option<Clock>,
+    /// Clock reading when the wait started.
+    started_us: u32,
+}
+
+impl Countdown {
+    pub(crate) const SLICE_US: u32 = 100;
+
+    #[inline]
+    pub(crate) fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+        self.start(self.remaining_us);
+    }
+
+    #[inline]
+    pub(crate) fn clock(&self) -> Option<Clock> {
+        self.clock
+    }
+
+    #[inline]
+    pub(crate) fn start(&mut self, us: u32) {
+        self.remaining_us = us;
+        if let Some(clock) = self.clock {
+            self.started_us = clock();
+        }
+    }
+
+    /// Time left, once the time measured by the clock is credited.
+    fn left(&self) -> u32 {
+        match self.clock {
+            Some(clock) => self
+                .remaining_us
+                .saturating_sub(clock().wrapping_sub(self.started_us)),
+            None => self.remaining_us,
+        }
+    }
+
+    /// Returns the time left and clears it, for callers that wait it out in one go.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub(crate) fn take(&mut self) -> u32 {
+        let left = self.left();
+        self.remaining_us = 0;
+        left
+    }
+
+    pub(crate) fn poll(&mut self, delay: &mut impl DelayMicros) -> nb::Result<(), Infallible> {
+        if self.remaining_us == 0 {
+            return Ok(());
+        }
+        if self.clock.is_some() {
+            if self.left() == 0 {
+                self.remaining_us = 0;
+            }
+        } else {
+            let slice = self.remaining_us.min(Self::SLICE_US);
+            delay.delay_us(slice);
+            self.remaining_us -= slice;
+        }
+        if self.remaining_us == 0 {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+/// Port value driving the expander or shift register `lines` wired to DB4–DB7 with `nibble`.
+pub(crate) const fn data_mask(lines: &[u8; 4], nibble: u8) -> u16 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < 4 {
+        if nibble & (1 << i) != 0 {
+            mask |= 1 << lines[i];
+        }
+        i += 1;
+    }
+    mask
+}
+
+/// Nibble read back from the `lines` wired to DB4–DB7 in `port`.
+pub(crate) const fn nibble(lines: &[u8; 4], port: u16) -> u8 {
+    let mut nibble = 0;
+    let mut i = 0;
+    while i < 4 {
+        if port & (1 << lines[i]) != 0 {
+            nibble |= 1 << i;
+        }
+        i += 1;
+    }
+    nibble
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use hd44780_nb::asynch::AsyncLcd;
+use hd44780_nb::emulator::Emulator;
+use hd44780_nb::geometry::Geometry;
+use hd44780_nb::instr::{DisplayControl, EntryModeSet, FunctionSet, SetDdramAddr};
+
+/// Polls `future` to completion, which must never have to wait for a wake-up.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn async_lcd_prints_through_emulator() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = AsyncLcd::new(emulator.pins(), emulator.delay());
+    block_on(async {
+        assert!(lcd
+            .init(FunctionSet::default(), EntryModeSet::default())
+            .await
+            .is_ok());
+        let display_on = DisplayControl {
+            display: true,
+            ..Default::default()
+        };
+        assert!(lcd.write(display_on.compile().into()).await.is_ok());
+        assert!(lcd.print("Hello").await.is_ok());
+        let second_line = SetDdramAddr::new_masked(0x40);
+        assert!(lcd.write(second_line.compile().into()).await.is_ok());
+        assert!(lcd.print("async").await.is_ok());
+
+        assert_eq!(lcd.state().await.ok().unwrap().addr(), 0x45);
+    });
+    assert!(emulator.is_four_bit());
+    assert_eq!(emulator.screen(), ["Hello           ", "async           "]);
+    assert_eq!(emulator.dropped_writes(), 0);
+    assert_eq!(emulator.violations(), []);
+}
@@ -0,0 +1,91 @@
+use hd44780_nb::charset::{Charset, Encoded, Rom};
+
+fn codes(charset: Charset, text: &str) -> Vec<u8> {
+    text.chars().flat_map(|c| charset.codes(c)).collect()
+}
+
+#[test]
+fn maps_characters_to_rom_tables() {
+    let cases = [
+        (Rom::A00, 'A', Some(0x41)),
+        (Rom::A00, '}', Some(0x7D)),
+        (Rom::A00, '¥', Some(0x5C)),
+        (Rom::A00, '→', Some(0x7E)),
+        (Rom::A00, '←', Some(0x7F)),
+        (Rom::A00, 'ｱ', Some(0xB1)),
+        (Rom::A00, 'ﾟ', Some(0xDF)),
+        (Rom::A00, '°', Some(0xDF)),
+        (Rom::A00, 'µ', Some(0xE4)),
+        (Rom::A00, 'ö', Some(0xEF)),
+        (Rom::A00, '円', Some(0xFC)),
+        (Rom::A00, '█', Some(0xFF)),
+        (Rom::A00, '\\', None),
+        (Rom::A00, '~', None),
+        (Rom::A00, 'é', None),
+        (Rom::A02, '\\', Some(0x5C)),
+        (Rom::A02, '~', Some(0x7E)),
+        (Rom::A02, 'Ж', Some(0x82)),
+        (Rom::A02, '♥', Some(0x9D)),
+        (Rom::A02, '∩', Some(0x9F)),
+        (Rom::A02, '¥', Some(0xA5)),
+        (Rom::A02, '§', Some(0xA7)),
+        (Rom::A02, '«', Some(0xAB)),
+        (Rom::A02, 'é', Some(0xE9)),
+        (Rom::A02, 'ÿ', Some(0xFF)),
+        (Rom::A02, '¨', None),
+        (Rom::A02, 'ｱ', None),
+    ];
+    for (rom, c, code) in cases {
+        let encoded = rom.encode(c).map(|codes| codes.collect::<Vec<_>>());
+        assert_eq!(encoded, code.map(|code| vec![code]), "{:?} on {:?}", c, rom);
+    }
+}
+
+#[test]
+fn falls_back_or_synthesizes_missing_characters() {
+    assert_eq!(codes(Charset::new(Rom::A02), "a€b"), b"a?b");
+    let charset = Charset::new(Rom::A02).with_fallback(0xFF);
+    assert_eq!(codes(charset, "a€b"), [b'a', 0xFF, b'b']);
+
+    let synthesizing = Charset::new(Rom::A00).with_synthesis(true);
+    assert!(matches!(synthesizing.encode('\\'), Encoded::Glyph(_)));
+    assert!(matches!(synthesizing.encode('~'), Encoded::Glyph(_)));
+    // Outputs that cannot synthesize get the fallback, as do characters without a glyph.
+    assert_eq!(codes(synthesizing, "\\~€"), b"???");
+    assert!(matches!(
+        synthesizing.encode('€'),
+        Encoded::Codes(codes) if codes.eq([b'?'])
+    ));
+    assert!(matches!(
+        Charset::new(Rom::A00).encode('\\'),
+        Encoded::Codes(codes) if codes.eq([b'?'])
+    ));
+    assert!(matches!(
+        Charset::new(Rom::A02).with_synthesis(true).encode('\\'),
+        Encoded::Codes(codes) if codes.eq([0x5C])
+    ));
+}
+
+#[test]
+fn splits_full_width_kana_into_half_width_codes() {
+    let cases: [(&str, &[u8]); 12] = [
+        ("ア", &[0xB1]),
+        ("が", &[0xB6, 0xDE]),
+        ("ガ", &[0xB6, 0xDE]),
+        ("ぱ", &[0xCA, 0xDF]),
+        ("ヴ", &[0xB3, 0xDE]),
+        ("ヺ", &[0xA6, 0xDE]),
+        ("ゃ", &[0xAC]),
+        ("ン", &[0xDD]),
+        ("ヵ", &[0xB6]),
+        ("ー", &[0xB0]),
+        ("。「」、・", &[0xA1, 0xA2, 0xA3, 0xA4, 0xA5]),
+        ("゛゜", &[0xDE, 0xDF]),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(codes(Charset::new(Rom::A00), text), expected, "{:?}", text);
+    }
+    // Already half-width, and missing from the European ROM.
+    assert_eq!(codes(Charset::new(Rom::A00), "ｶﾞ"), [0xB6, 0xDE]);
+    assert_eq!(codes(Charset::new(Rom::A02), "が"), b"?");
+}
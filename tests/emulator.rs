@@ -0,0 +1,399 @@
+use std::convert::Infallible;
+
+use hd44780_nb::dual::DualEnable;
+use hd44780_nb::emulator::{
+    Emulator, EmulatorBus, EmulatorDelay, EmulatorPin, Timing, Transaction, Violation,
+};
+use hd44780_nb::geometry::Geometry;
+use hd44780_nb::glyph::{Glyph, GlyphCache, GlyphCacheError};
+use hd44780_nb::hal::delay::DelayNs;
+use hd44780_nb::hal::digital::{ErrorType, OutputPin, PinState};
+use hd44780_nb::instr::{
+    CursorDisplayShift, Direction, DisplayControl, EntryModeSet, Font, FunctionSet, Lines,
+    ShiftTarget,
+};
+use hd44780_nb::text::TextWriter;
+use hd44780_nb::ufmt::uWrite;
+use hd44780_nb::{nb, DataBus, Grounded, Interface, Lcd, LcdPins};
+
+fn init<I: Interface, D: DelayNs>(lcd: Lcd<I, D>) -> Lcd<I, D> {
+    let mut init = lcd.init(FunctionSet::default(), EntryModeSet::default());
+    assert!(nb::block!(init.poll()).is_ok());
+    let mut lcd = init.finish().ok().unwrap();
+    let display_on = DisplayControl {
+        display: true,
+        ..Default::default()
+    };
+    assert!(nb::block!(lcd.write(display_on.compile().into())).is_ok());
+    lcd
+}
+
+#[test]
+fn prints_through_four_bit_bus_with_busy_flag() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    assert!(emulator.is_four_bit());
+
+    assert!(lcd.write_str("Hello").is_ok());
+    assert!(nb::block!(lcd.set_cursor(1, 3)).is_ok());
+    assert!(lcd.write_str("world!").is_ok());
+    assert_eq!(emulator.screen(), ["Hello           ", "   world!       "]);
+    assert_eq!(lcd.cursor().ok().unwrap(), Some((1, 9)));
+    assert_eq!(emulator.dropped_writes(), 0);
+}
+
+#[test]
+fn waits_out_execution_times_without_rw() {
+    let emulator = Emulator::new(Geometry::LCD_20X4);
+    let pins = LcdPins::new(
+        emulator.register_selection(),
+        Grounded,
+        emulator.enable(),
+        emulator.eight_bit_bus(),
+    );
+    let lcd = init(pins.with_delay(emulator.delay())).with_geometry(Geometry::LCD_20X4);
+    assert!(!emulator.is_four_bit());
+
+    let mut writer = TextWriter::new(lcd);
+    assert!(writer
+        .write_str("first\nsecond\nthird\nfourth\nfifth")
+        .is_ok());
+    assert_eq!(
+        emulator.screen(),
+        [
+            "second              ",
+            "third               ",
+            "fourth              ",
+            "fifth               ",
+        ]
+    );
+    assert_eq!(emulator.dropped_writes(), 0);
+}
+
+#[test]
+fn shifts_display_within_each_line() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    assert!(lcd.write_str("Hello").is_ok());
+    assert!(nb::block!(lcd.set_cursor(1, 0)).is_ok());
+    assert!(lcd.write_str("World").is_ok());
+
+    let shift = CursorDisplayShift {
+        target: ShiftTarget::Display,
+        direction: Direction::Left,
+    };
+    assert!(nb::block!(lcd.write(shift.compile().into())).is_ok());
+    assert_eq!(emulator.display_shift(), 1);
+    assert_eq!(emulator.screen(), ["ello            ", "orld            "]);
+}
+
+#[test]
+fn two_line_mode_prints_glyphs_in_5x8_dots() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let function_set = FunctionSet {
+        lines: Lines::Two,
+        font: Font::Dots5x10,
+        ..Default::default()
+    };
+    let mut init = emulator
+        .pins()
+        .with_delay(emulator.delay())
+        .init(function_set, EntryModeSet::default());
+    assert!(nb::block!(init.poll()).is_ok());
+    let mut lcd = init.finish().ok().unwrap();
+    // Slot 1 is code 1 in 5x8 dots, where 5x10 dots would have used code 2.
+    assert!(nb::block!(lcd.print_glyph(1)).is_ok());
+    assert_eq!(emulator.code_at(0, 0), 0x01);
+}
+
+#[test]
+fn uploads_glyphs_in_display_font() {
+    let rows: [u8; 10] = core::array::from_fn(|i| i as u8 + 1);
+
+    // A 5x10 glyph on a 5x8 display loses its last two rows and stays in its own slot.
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    assert!(lcd.upload_glyph(5, &Glyph::Dots5x10(rows)).is_ok());
+    assert!(nb::block!(lcd.print_glyph(5)).is_ok());
+    let mut cgram = [0; 64];
+    cgram[40..48].copy_from_slice(&rows[..8]);
+    assert_eq!(emulator.cgram(), cgram);
+    assert_eq!(emulator.code_at(0, 0), 0x05);
+
+    // A 5x8 glyph on a one-line 5x10 display gets blank rows below.
+    let emulator = Emulator::new(Geometry::LCD_8X1);
+    let function_set = FunctionSet {
+        lines: Lines::One,
+        font: Font::Dots5x10,
+        ..Default::default()
+    };
+    let mut init = emulator
+        .pins()
+        .with_delay(emulator.delay())
+        .init(function_set, EntryModeSet::default());
+    assert!(nb::block!(init.poll()).is_ok());
+    let mut lcd = init.finish().ok().unwrap();
+    let glyph = Glyph::Dots5x8([0x1F; 8]);
+    assert!(lcd.upload_glyph(1, &glyph).is_ok());
+    assert!(nb::block!(lcd.print_glyph(1)).is_ok());
+    let mut cgram = [0; 64];
+    cgram[16..24].fill(0x1F);
+    assert_eq!(emulator.cgram(), cgram);
+    assert_eq!(emulator.code_at(0, 0), 0x02);
+}
+
+#[test]
+fn glyph_cache_evicts_least_recently_used_off_screen_glyph() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    let glyph = |id: u8| Glyph::Dots5x8([id; 8]);
+    let mut cache = GlyphCache::new();
+    for id in 0..8 {
+        assert!(cache.print(&mut lcd, id, &glyph(id)).is_ok());
+        assert_eq!(cache.slot(id), Some(id));
+    }
+    assert!(matches!(
+        cache.print(&mut lcd, 8, &glyph(8)),
+        Err(nb::Error::Other(GlyphCacheError::NoFreeSlot))
+    ));
+
+    // Of the glyphs gone from the screen, 2 was used before 5.
+    cache.forget(5);
+    cache.forget(2);
+    assert!(cache.print(&mut lcd, 8, &glyph(8)).is_ok());
+    assert_eq!(cache.slot(8), Some(2));
+    assert_eq!(cache.slot(2), None);
+
+    // Showing 0 again makes it the most recently used.
+    assert!(cache.print(&mut lcd, 0, &glyph(0)).is_ok());
+    cache.forget(0);
+    cache.forget(0);
+    assert!(cache.print(&mut lcd, 9, &glyph(9)).is_ok());
+    assert_eq!(cache.slot(9), Some(5));
+    assert_eq!(cache.slot(0), Some(0));
+
+    let cgram = emulator.cgram();
+    let expected = [0, 1, 8, 3, 4, 9, 6, 7];
+    for (slot, id) in expected.into_iter().enumerate() {
+        assert_eq!(cgram[slot * 8..][..8], [id; 8]);
+    }
+    let codes: Vec<_> = (0..11).map(|col| emulator.code_at(0, col)).collect();
+    assert_eq!(codes, [0, 1, 2, 3, 4, 5, 6, 7, 2, 0, 5]);
+
+    // Every slot is on screen again once 0 is shown.
+    assert!(cache.print(&mut lcd, 0, &glyph(0)).is_ok());
+    assert!(matches!(
+        cache.print(&mut lcd, 10, &glyph(10)),
+        Err(nb::Error::Other(GlyphCacheError::NoFreeSlot))
+    ));
+}
+
+#[test]
+fn glyph_cache_keeps_mixed_font_glyphs_in_their_slots() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    let mut cache = GlyphCache::new();
+    for id in 0..8 {
+        let glyph = if id % 2 == 0 {
+            Glyph::Dots5x10([id + 1; 10])
+        } else {
+            Glyph::Dots5x8([id + 1; 8])
+        };
+        assert!(cache.print(&mut lcd, id, &glyph).is_ok());
+        assert_eq!(cache.slot(id), Some(id));
+    }
+
+    // Every glyph takes eight rows of a 5x8 display, whatever its own font.
+    let cgram = emulator.cgram();
+    for slot in 0..8 {
+        assert_eq!(cgram[slot * 8..][..8], [slot as u8 + 1; 8]);
+    }
+    let codes: Vec<_> = (0..8).map(|col| emulator.code_at(0, col)).collect();
+    assert_eq!(codes, [0, 1, 2, 3, 4, 5, 6, 7]);
+}
+
+/// RS, the data lines or the clock of a display made of two controllers, wired to both.
+struct Both<T>(T, T);
+
+impl ErrorType for Both<EmulatorPin> {
+    type Error = Infallible;
+}
+
+impl OutputPin for Both<EmulatorPin> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low()?;
+        self.1.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high()?;
+        self.1.set_high()
+    }
+}
+
+impl DataBus for Both<EmulatorBus<4>> {
+    type Error = Infallible;
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        let states: Vec<_> = states.collect();
+        self.0.write_pins_now(states.iter().copied())?;
+        self.1.write_pins_now(states.into_iter())
+    }
+
+    // Never called with RW grounded.
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        self.0.read_pins_now()
+    }
+}
+
+impl DelayNs for Both<EmulatorDelay> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_ns(ns);
+        self.1.delay_ns(ns);
+    }
+}
+
+type DualLcd = Lcd<
+    LcdPins<
+        Both<EmulatorPin>,
+        Grounded,
+        DualEnable<EmulatorPin, EmulatorPin>,
+        Both<EmulatorBus<4>>,
+    >,
+    Both<EmulatorDelay>,
+>;
+
+/// A 40x4 display made of two emulated 40x2 controllers, initialized and turned on.
+fn dual() -> (Emulator, Emulator, DualLcd) {
+    let (top, bottom) = (
+        Emulator::new(Geometry::LCD_40X2),
+        Emulator::new(Geometry::LCD_40X2),
+    );
+    let pins = LcdPins::new(
+        Both(top.register_selection(), bottom.register_selection()),
+        Grounded,
+        DualEnable::new(top.enable(), bottom.enable()),
+        Both(top.four_bit_bus(), bottom.four_bit_bus()),
+    );
+    let lcd = pins
+        .with_delay(Both(top.delay(), bottom.delay()))
+        .with_geometry(Geometry::LCD_40X4);
+    (top, bottom, init(lcd))
+}
+
+#[test]
+fn scrolls_all_four_rows_of_two_controllers() {
+    let (top, bottom, lcd) = dual();
+    let mut writer = TextWriter::new(lcd);
+    assert!(writer.write_str("r0\nr1\nr2\nr3\nr4").is_ok());
+    let row = |text| format!("{:<40}", text);
+    assert_eq!(top.screen(), [row("r1"), row("r2")]);
+    assert_eq!(bottom.screen(), [row("r3"), row("r4")]);
+    assert_eq!(top.dropped_writes() + bottom.dropped_writes(), 0);
+}
+
+#[test]
+fn shows_cursor_on_selected_controller_only() {
+    let (top, bottom, mut lcd) = dual();
+    let cursor_on = DisplayControl {
+        display: true,
+        cursor: true,
+        blink: true,
+    };
+    let display_on = DisplayControl {
+        display: true,
+        ..Default::default()
+    };
+    assert!(nb::block!(lcd.write(cursor_on.compile().into())).is_ok());
+    assert_eq!(top.display_control(), cursor_on);
+    assert_eq!(bottom.display_control(), display_on);
+
+    // The cursor follows the controller once something is written to it.
+    assert!(nb::block!(lcd.set_cursor(3, 5)).is_ok());
+    assert_eq!(top.display_control(), display_on);
+    assert_eq!(bottom.display_control(), cursor_on);
+    assert_eq!(bottom.address_counter(), 0x45);
+
+    assert!(nb::block!(lcd.set_cursor(0, 0)).is_ok());
+    assert_eq!(top.display_control(), cursor_on);
+    assert_eq!(bottom.display_control(), display_on);
+    assert_eq!(top.dropped_writes() + bottom.dropped_writes(), 0);
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[test]
+fn drops_writes_sent_too_early() {
+    let emulator = Emulator::default();
+    let pins = LcdPins::new(
+        emulator.register_selection(),
+        Grounded,
+        emulator.enable(),
+        emulator.four_bit_bus(),
+    );
+    let mut init = pins
+        .with_delay(NoDelay)
+        .init(FunctionSet::default(), EntryModeSet::default());
+    assert!(nb::block!(init.poll()).is_ok());
+    // Nothing gets through the internal reset, and the controller stays in 8-bit mode.
+    assert!(emulator.dropped_writes() > 0);
+    assert!(emulator
+        .violations()
+        .iter()
+        .any(|violation| violation.timing == Timing::Busy));
+    assert!(!emulator.is_four_bit());
+}
+
+#[test]
+fn lcd_pins_meet_bus_timing() {
+    let emulator = Emulator::new(Geometry::LCD_16X2);
+    let mut lcd = init(emulator.pins().with_delay(emulator.delay()));
+    assert!(lcd.write_str("Hi").is_ok());
+    assert_eq!(lcd.cursor().ok().unwrap(), Some((0, 2)));
+    assert_eq!(emulator.violations(), []);
+}
+
+#[test]
+fn reports_violations_with_transaction() {
+    let emulator = Emulator::default();
+    emulator.delay().delay_ms(20);
+    let (mut rs, mut enable, mut bus) = (
+        emulator.register_selection(),
+        emulator.enable(),
+        emulator.four_bit_bus(),
+    );
+    // Data pulse with no setup time and E high for no time at all.
+    assert!(rs.set_high().is_ok());
+    assert!(bus
+        .write_pins_now([PinState::Low, PinState::Low, PinState::High, PinState::Low].into_iter())
+        .is_ok());
+    assert!(enable.set_high().is_ok());
+    assert!(enable.set_low().is_ok());
+
+    let transaction = Transaction {
+        at_ns: 20_000_000,
+        rs: true,
+        rw: false,
+        data: 0x40,
+    };
+    let violation = |timing| Violation {
+        timing,
+        at_ns: 20_000_000,
+        transaction,
+    };
+    assert_eq!(
+        emulator.violations(),
+        [
+            violation(Timing::AddressSetup),
+            violation(Timing::EnablePulseWidth),
+            violation(Timing::DataSetup),
+        ]
+    );
+}
@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use hd44780_nb::hal::delay::DelayNs;
+use hd44780_nb::hal::spi::{ErrorType, Operation, SpiDevice};
+use hd44780_nb::hc595::{Hc595, Hc595Mapping};
+use hd44780_nb::instr::{Clear, Deliverable};
+use hd44780_nb::{nb, Interface};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Event {
+    /// A byte latched onto Q0–Q7 at the end of a transaction.
+    Latch(u8),
+    Delay(u32),
+}
+
+type Events = Rc<RefCell<Vec<Event>>>;
+
+struct MockSpi(Events);
+
+impl ErrorType for MockSpi {
+    type Error = Infallible;
+}
+
+impl SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let events = bytes.iter().map(|&byte| Event::Latch(byte));
+                    self.0.borrow_mut().extend(events);
+                }
+                _ => panic!("unexpected SPI operation"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records every delay in nanoseconds.
+struct MockDelay(Events);
+
+impl DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().push(Event::Delay(ns));
+    }
+}
+
+fn take(events: &Events) -> Vec<Event> {
+    std::mem::take(&mut events.borrow_mut())
+}
+
+#[test]
+fn write_strobes_e_after_lines_settle_then_waits() {
+    let events = Events::default();
+    let mut hc595 = Hc595::new(MockSpi(events.clone()), Hc595Mapping::default());
+    let mut delay = MockDelay(events.clone());
+
+    assert!(hc595.write(&mut delay, Deliverable::Data(0x41)).is_ok());
+    assert_eq!(
+        take(&events),
+        [
+            // Upper nibble 0x4 on Q6 with RS on Q0 and the backlight on Q2, latched with E
+            // on Q1 low, then high, then low again.
+            Event::Latch(0x45),
+            Event::Latch(0x47),
+            Event::Delay(1_000),
+            Event::Latch(0x45),
+            Event::Delay(1_000),
+            // Lower nibble 0x1 on Q4.
+            Event::Latch(0x15),
+            Event::Latch(0x17),
+            Event::Delay(1_000),
+            Event::Latch(0x15),
+            Event::Delay(1_000),
+        ]
+    );
+
+    // The next write first waits out the execution time of the data write.
+    let clear = Clear::compile().into();
+    assert!(hc595.write(&mut delay, clear).is_ok());
+    let sent = take(&events);
+    assert_eq!(sent[..2], [Event::Delay(37_000), Event::Latch(0x04)]);
+
+    // Clear takes longer, waited out in slices of at most 100 µs before anything is latched.
+    let mut blocked = 0;
+    while let Err(nb::Error::WouldBlock) = hc595.write(&mut delay, Deliverable::Data(0x41)) {
+        blocked += 1;
+    }
+    assert_eq!(blocked, 15);
+    let sent = take(&events);
+    let slices: Vec<_> = sent
+        .iter()
+        .map_while(|event| match event {
+            Event::Delay(ns) => Some(*ns),
+            Event::Latch(_) => None,
+        })
+        .collect();
+    assert_eq!(slices.len(), 16);
+    assert!(slices.iter().all(|&ns| ns <= 100_000));
+    assert_eq!(slices.iter().sum::<u32>(), Clear::EXECUTION_TIME_US * 1_000);
+    assert_eq!(sent[16], Event::Latch(0x45));
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use hd44780_nb::hal::delay::DelayNs;
+use hd44780_nb::hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+use hd44780_nb::instr::Deliverable;
+use hd44780_nb::mcp230xx::{rgb_shield, Mcp230xx, Mcp230xxMapping, Variant};
+use hd44780_nb::{Interface, Lcd, ReadInterface};
+
+/// Records the bytes of every write, and answers reads from `reads`.
+#[derive(Default)]
+struct MockI2c {
+    writes: Vec<Vec<u8>>,
+    reads: VecDeque<u16>,
+}
+
+impl ErrorType for MockI2c {
+    type Error = Infallible;
+}
+
+impl I2c for MockI2c {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        assert_eq!(address, 0x20);
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.writes.push(bytes.to_vec()),
+                Operation::Read(buffer) => {
+                    let port = self.reads.pop_front().unwrap().to_le_bytes();
+                    buffer.copy_from_slice(&port[..buffer.len()]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[test]
+fn mcp23008_latches_lines_before_enable() {
+    let mut mcp = Mcp230xx::new(
+        MockI2c::default(),
+        Mcp230xx::<MockI2c>::DEFAULT_ADDRESS,
+        Variant::Mcp23008,
+        Mcp230xxMapping::ADAFRUIT_BACKPACK,
+    );
+    assert!(mcp.write(&mut NoDelay, Deliverable::Data(0x41)).is_ok());
+    assert_eq!(
+        mcp.release().writes,
+        [
+            // OLAT cleared, then the display pins turned into outputs with GP0 and GP7 left
+            // as inputs.
+            vec![0x0A, 0x00],
+            vec![0x00, 0x81],
+            // Upper nibble 0x4 on GP5 with RS on GP1, E on GP2.
+            vec![0x0A, 0x22],
+            vec![0x0A, 0x26],
+            vec![0x0A, 0x22],
+            // Lower nibble 0x1 on GP3.
+            vec![0x0A, 0x0A],
+            vec![0x0A, 0x0E],
+            vec![0x0A, 0x0A],
+        ]
+    );
+}
+
+#[test]
+fn mcp23017_switches_data_lines_to_read_busy_flag() {
+    let mut i2c = MockI2c::default();
+    // Busy with the address counter at 5: DB7 on GPB1, then DB6 and DB4 on GPB2 and GPB4.
+    i2c.reads.extend([0x0200, 0x1400]);
+    let mut mcp = Mcp230xx::new(
+        i2c,
+        Mcp230xx::<MockI2c>::DEFAULT_ADDRESS,
+        Variant::Mcp23017,
+        Mcp230xxMapping::ADAFRUIT_RGB_SHIELD,
+    );
+    let state = mcp.state(&mut NoDelay).ok().unwrap();
+    assert!(state.busy());
+    assert_eq!(state.addr(), 5);
+
+    let mut lcd = Lcd::new(mcp, NoDelay);
+    assert!(lcd
+        .interface_mut()
+        .write_extra(rgb_shield::RED | 1 << 15, rgb_shield::RED | 1 << 15)
+        .is_ok());
+    let (mcp, _) = lcd.into();
+    assert_eq!(
+        mcp.release().writes,
+        [
+            vec![0x14, 0x00, 0x00],
+            vec![0x00, 0xFF, 0x01],
+            // DB4–DB7 on GPB1–GPB4 become inputs, RS goes low and RW high.
+            vec![0x00, 0xFF, 0x1F],
+            vec![0x14, 0x00, 0x40],
+            // Both nibbles are read from GPIO while E is high.
+            vec![0x14, 0x00, 0x60],
+            vec![0x12],
+            vec![0x14, 0x00, 0x40],
+            vec![0x14, 0x00, 0x60],
+            vec![0x12],
+            vec![0x14, 0x00, 0x40],
+            vec![0x14, 0x00, 0x00],
+            vec![0x00, 0xFF, 0x01],
+            // RS on GPB7 belongs to the display and is left alone.
+            vec![0x14, 0x40, 0x00],
+        ]
+    );
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use hd44780_nb::hal::delay::DelayNs;
+use hd44780_nb::hal::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+use hd44780_nb::instr::Deliverable;
+use hd44780_nb::pcf8574::{Pcf8574, Pcf8574Mapping};
+use hd44780_nb::{nb, Interface, ReadInterface};
+
+/// Records the bytes of every write, and answers reads from `reads`.
+#[derive(Default)]
+struct MockI2c {
+    writes: Vec<Vec<u8>>,
+    reads: VecDeque<u8>,
+}
+
+impl ErrorType for MockI2c {
+    type Error = Infallible;
+}
+
+impl I2c for MockI2c {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        assert_eq!(address, 0x27);
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.writes.push(bytes.to_vec()),
+                Operation::Read(buffer) => buffer.fill(self.reads.pop_front().unwrap()),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// A backpack whose port reads give `reads` in turn.
+fn pcf8574(mapping: Pcf8574Mapping, reads: &[u8]) -> Pcf8574<MockI2c> {
+    let i2c = MockI2c {
+        writes: Vec::new(),
+        reads: reads.iter().copied().collect(),
+    };
+    Pcf8574::new(i2c, Pcf8574::<MockI2c>::PCF8574_ADDRESS, mapping)
+}
+
+#[test]
+fn write_pulses_both_nibbles_after_busy_flag() {
+    let mut pcf = pcf8574(Pcf8574Mapping::COMMON, &[0x00, 0x00]);
+    assert!(pcf.write(&mut NoDelay, Deliverable::Data(0x41)).is_ok());
+    assert_eq!(
+        pcf.release().writes,
+        [
+            // RW on P1 and the backlight on P3 with DB4–DB7 released high, E pulsed on P2
+            // for each nibble of the busy flag read.
+            vec![0xFA, 0xFE],
+            vec![0xFA],
+            vec![0xFA, 0xFE],
+            vec![0xFA],
+            // Upper nibble 0x4 on P6 then lower nibble 0x1 on P4, with RS on P0.
+            vec![0x49, 0x4D, 0x49, 0x19, 0x1D, 0x19],
+        ]
+    );
+}
+
+#[test]
+fn state_reads_high_nibble_first() {
+    // Busy with the address counter at 5: DB7 on P7, then DB6 and DB4 on P6 and P4.
+    let mut pcf = pcf8574(Pcf8574Mapping::COMMON, &[0x80, 0x50, 0x80, 0x00]);
+    let state = pcf.state(&mut NoDelay).ok().unwrap();
+    assert!(state.busy());
+    assert_eq!(state.addr(), 5);
+
+    // Nothing is written while the controller is busy.
+    assert!(matches!(
+        pcf.write(&mut NoDelay, Deliverable::Data(0x41)),
+        Err(nb::Error::WouldBlock)
+    ));
+    let i2c = pcf.release();
+    assert!(i2c.reads.is_empty());
+    assert!(i2c.writes.iter().all(|bytes| bytes[0] == 0xFA));
+}
+
+#[test]
+fn mjkdz_backlight_is_active_low() {
+    let mut pcf = pcf8574(Pcf8574Mapping::MJKDZ, &[]);
+    assert!(pcf.backlight());
+    assert!(pcf.write_nibble(&mut NoDelay, 0x3).is_ok());
+    assert!(pcf.set_backlight(false).is_ok());
+    assert!(pcf.write_nibble(&mut NoDelay, 0x2).is_ok());
+    assert!(pcf.set_backlight(true).is_ok());
+    assert_eq!(
+        pcf.release().writes,
+        [
+            // DB4–DB7 on P0–P3 and E on P4, with P7 low while the backlight is on.
+            vec![0x03, 0x13, 0x03],
+            vec![0x80],
+            vec![0x82, 0x92, 0x82],
+            vec![0x00],
+        ]
+    );
+}
@@ -0,0 +1,455 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use hd44780_nb::bus::{FlexBus, FlexPin};
+use hd44780_nb::framebuffer::FrameBuffer;
+use hd44780_nb::geometry::Geometry;
+use hd44780_nb::hal::delay::DelayNs;
+use hd44780_nb::hal::digital::{ErrorType, InputPin, OutputPin, PinState};
+use hd44780_nb::instr::{Clear, Deliverable, FunctionSet, SetDdramAddr};
+use hd44780_nb::shared::{BusLines, SharedLcdPins};
+use hd44780_nb::trace::{Line as TraceLine, Trace, TracedBus, TracedDelay, TracedPin};
+use hd44780_nb::{nb, DataBus, Grounded, Interface, LcdPins, ReadInterface};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Pulse {
+    rs: PinState,
+    rw: PinState,
+    nibble: u8,
+}
+
+#[derive(Default)]
+struct Wires {
+    rs: Option<PinState>,
+    rw: Option<PinState>,
+    enable: bool,
+    data: u8,
+    /// Data lines currently switched to inputs, for buses that switch direction.
+    inputs: u8,
+    pulses: Vec<Pulse>,
+    reads: VecDeque<u8>,
+}
+
+type Shared = Rc<RefCell<Wires>>;
+
+#[derive(Clone, Copy)]
+enum Line {
+    RegisterSelection,
+    ReadWrite,
+    Enable,
+}
+
+struct MockPin(Shared, Line);
+
+impl ErrorType for MockPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::Low)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::High)
+    }
+
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        let mut wires = self.0.borrow_mut();
+        match self.1 {
+            Line::RegisterSelection => wires.rs = Some(state),
+            Line::ReadWrite => wires.rw = Some(state),
+            Line::Enable => {
+                let high = state == PinState::High;
+                if wires.enable && !high {
+                    let pulse = Pulse {
+                        rs: wires.rs.unwrap(),
+                        rw: wires.rw.unwrap_or(PinState::Low),
+                        nibble: wires.data,
+                    };
+                    wires.pulses.push(pulse);
+                }
+                wires.enable = high;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct MockBus(Shared);
+
+impl DataBus for MockBus {
+    type Error = Infallible;
+
+    fn write_pins_now(
+        &mut self,
+        states: impl ExactSizeIterator<Item = PinState>,
+    ) -> Result<(), Self::Error> {
+        assert_eq!(states.len(), 4);
+        self.0.borrow_mut().data = states
+            .enumerate()
+            .map(|(i, state)| ((state == PinState::High) as u8) << i)
+            .sum();
+        Ok(())
+    }
+
+    fn read_pins_now(&mut self) -> Result<impl ExactSizeIterator<Item = PinState>, Self::Error> {
+        let mut wires = self.0.borrow_mut();
+        // The controller only drives the data lines while E is high.
+        assert!(wires.enable, "data lines sampled with E low");
+        let nibble = wires.reads.pop_front().unwrap();
+        Ok((0..4).map(move |i| PinState::from(nibble & (1 << i) != 0)))
+    }
+}
+
+/// One of DB4–DB7 switching direction in place.
+struct MockFlexPin(Shared, u8);
+
+impl ErrorType for MockFlexPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockFlexPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::Low)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_state(PinState::High)
+    }
+
+    fn set_state(&mut self, state: PinState) -> Result<(), Self::Error> {
+        let mut wires = self.0.borrow_mut();
+        assert_eq!(wires.inputs & 1 << self.1, 0, "input pin driven");
+        wires.data &= !(1 << self.1);
+        wires.data |= ((state == PinState::High) as u8) << self.1;
+        Ok(())
+    }
+}
+
+impl InputPin for MockFlexPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        let mut wires = self.0.borrow_mut();
+        assert!(wires.enable, "data lines sampled with E low");
+        assert_ne!(wires.inputs & 1 << self.1, 0, "output pin sampled");
+        let nibble = *wires.reads.front().unwrap();
+        // DB7 is sampled last.
+        if self.1 == 3 {
+            wires.reads.pop_front();
+        }
+        Ok(nibble & 1 << self.1 != 0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+impl FlexPin for MockFlexPin {
+    fn set_as_input(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().inputs |= 1 << self.1;
+        Ok(())
+    }
+
+    fn set_as_output(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().inputs &= !(1 << self.1);
+        Ok(())
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+type Pins = LcdPins<MockPin, MockPin, MockPin, MockBus>;
+
+fn setup() -> (Shared, Pins) {
+    let wires = Shared::default();
+    let pins = LcdPins::new(
+        MockPin(wires.clone(), Line::RegisterSelection),
+        MockPin(wires.clone(), Line::ReadWrite),
+        MockPin(wires.clone(), Line::Enable),
+        MockBus(wires.clone()),
+    );
+    (wires, pins)
+}
+
+fn writes(wires: &Shared) -> Vec<Pulse> {
+    wires
+        .borrow()
+        .pulses
+        .iter()
+        .copied()
+        .filter(|p| p.rw == PinState::Low)
+        .collect()
+}
+
+#[test]
+fn state_reads_high_nibble_first() {
+    let (wires, mut pins) = setup();
+    wires.borrow_mut().reads.extend([0x8, 0x5, 0x4, 0x0]);
+
+    let state = pins.state(&mut NoDelay).ok().unwrap();
+    assert!(state.busy());
+    assert_eq!(state.addr(), 0x05);
+
+    let state = pins.state(&mut NoDelay).ok().unwrap();
+    assert!(!state.busy());
+    assert_eq!(state.addr(), 0x40);
+
+    let wires = wires.borrow();
+    assert_eq!(wires.pulses.len(), 4);
+    assert!(wires
+        .pulses
+        .iter()
+        .all(|p| p.rs == PinState::Low && p.rw == PinState::High));
+}
+
+#[test]
+fn flex_bus_reads_with_lines_switched_to_inputs() {
+    let wires = Shared::default();
+    let mut pins = LcdPins::new(
+        MockPin(wires.clone(), Line::RegisterSelection),
+        MockPin(wires.clone(), Line::ReadWrite),
+        MockPin(wires.clone(), Line::Enable),
+        FlexBus::new([0, 1, 2, 3].map(|i| MockFlexPin(wires.clone(), i))),
+    );
+    wires.borrow_mut().reads.extend([0x8, 0x5, 0x0, 0x0]);
+
+    let state = pins.state(&mut NoDelay).ok().unwrap();
+    assert!(state.busy());
+    assert_eq!(state.addr(), 0x05);
+    assert_eq!(wires.borrow().inputs, 0);
+
+    assert!(pins.write(&mut NoDelay, Deliverable::Data(b'A')).is_ok());
+    assert_eq!(
+        writes(&wires),
+        [
+            Pulse {
+                rs: PinState::High,
+                rw: PinState::Low,
+                nibble: 0x4,
+            },
+            Pulse {
+                rs: PinState::High,
+                rw: PinState::Low,
+                nibble: 0x1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn write_sends_high_nibble_first() {
+    let (wires, mut pins) = setup();
+    wires.borrow_mut().reads.extend([0x0, 0x0, 0x0, 0x0]);
+
+    let function_set = FunctionSet::default().compile();
+    assert!(pins.write(&mut NoDelay, function_set.into()).is_ok());
+    assert!(pins.write(&mut NoDelay, Deliverable::Data(b'A')).is_ok());
+
+    let instr = |nibble| Pulse {
+        rs: PinState::Low,
+        rw: PinState::Low,
+        nibble,
+    };
+    let data = |nibble| Pulse {
+        rs: PinState::High,
+        rw: PinState::Low,
+        nibble,
+    };
+    assert_eq!(
+        writes(&wires),
+        [instr(0x2), instr(0x8), data(0x4), data(0x1)]
+    );
+}
+
+#[test]
+fn write_blocks_while_busy() {
+    let (wires, mut pins) = setup();
+    wires.borrow_mut().reads.extend([0x8, 0x0, 0x0, 0x0]);
+
+    let clear = Clear::compile().into();
+    assert!(matches!(
+        pins.write(&mut NoDelay, clear),
+        Err(nb::Error::WouldBlock)
+    ));
+    assert!(writes(&wires).is_empty());
+
+    assert!(pins.write(&mut NoDelay, clear).is_ok());
+    assert_eq!(writes(&wires).len(), 2);
+    assert!(wires.borrow().reads.is_empty());
+}
+
+#[test]
+fn poll_flush_sends_one_transfer_per_call() {
+    let (wires, pins) = setup();
+    let mut lcd = pins.with_delay(NoDelay);
+    let mut frame = FrameBuffer::<2, 16>::new(lcd.charset());
+    frame.print(0, 0, "Hi");
+    frame.print(1, 3, "X");
+
+    let instr = |byte: u8| [(PinState::Low, byte >> 4), (PinState::Low, byte & 0x0F)];
+    let data = |byte: u8| [(PinState::High, byte >> 4), (PinState::High, byte & 0x0F)];
+    let expected = [instr(0x80), data(b'H'), data(b'i'), instr(0xC3), data(b'X')];
+    for (i, transfer) in expected.iter().enumerate() {
+        // A busy flag stall in the middle of the run leaves nothing half sent.
+        if i == 2 {
+            wires.borrow_mut().reads.extend([0x8, 0x0]);
+            assert!(matches!(
+                frame.poll_flush(&mut lcd),
+                Err(nb::Error::WouldBlock)
+            ));
+        }
+        wires.borrow_mut().pulses.clear();
+        wires.borrow_mut().reads.extend([0x0, 0x0]);
+        let result = frame.poll_flush(&mut lcd);
+        assert_eq!(result.is_ok(), i == expected.len() - 1);
+        let sent: Vec<_> = writes(&wires).iter().map(|p| (p.rs, p.nibble)).collect();
+        assert_eq!(sent, transfer);
+    }
+    assert!(!frame.is_dirty());
+}
+
+#[test]
+fn degenerate_geometry_is_clamped() {
+    let empty = Geometry::new(0, 0, [0x00; 4]);
+    assert_eq!((empty.rows(), empty.cols()), (1, 1));
+    let oversized = Geometry::new(9, 255, [0x00, 0x40, 0x14, 0x54]);
+    assert_eq!((oversized.rows(), oversized.cols()), (4, 80));
+    // Offsets past 7 bits are masked, and addresses past the last one wrap around.
+    let offset = Geometry::new(1, 80, [0xC0, 0, 0, 0]);
+    assert_eq!(offset.addr(0, 79), SetDdramAddr::new(0x0F));
+    assert_eq!(offset.position(0x7F), Some((0, 63)));
+
+    let (wires, pins) = setup();
+    wires.borrow_mut().reads.extend([0x0, 0x0]);
+    let mut lcd = pins.with_delay(NoDelay).with_geometry(empty);
+    assert!(lcd.set_cursor(2, 5).is_ok());
+    let sent: Vec<_> = writes(&wires).iter().map(|p| p.nibble).collect();
+    assert_eq!(sent, [0x8, 0x0]);
+}
+
+#[test]
+fn shared_bus_tracks_busy_per_display() {
+    let wires = Shared::default();
+    let bus = RefCell::new(BusLines::new(
+        MockPin(wires.clone(), Line::RegisterSelection),
+        Grounded,
+        MockBus(wires.clone()),
+    ));
+    let mut first = SharedLcdPins::new(&bus, MockPin(wires.clone(), Line::Enable));
+    let mut second = SharedLcdPins::new(&bus, MockPin(wires.clone(), Line::Enable));
+
+    let clear = Clear::compile().into();
+    assert!(first.write(&mut NoDelay, clear).is_ok());
+    assert!(matches!(
+        first.write(&mut NoDelay, clear),
+        Err(nb::Error::WouldBlock)
+    ));
+    // The second display is idle and takes the bus while the first is still clearing.
+    assert!(second.write(&mut NoDelay, clear).is_ok());
+    assert_eq!(writes(&wires).len(), 4);
+}
+
+thread_local! {
+    static NOW_US: Cell<u32> = const { Cell::new(u32::MAX - 1_000) };
+}
+
+fn now_us() -> u32 {
+    NOW_US.get()
+}
+
+#[test]
+fn grounded_write_waits_against_clock() {
+    let wires = Shared::default();
+    let mut pins = LcdPins::new(
+        MockPin(wires.clone(), Line::RegisterSelection),
+        Grounded,
+        MockPin(wires.clone(), Line::Enable),
+        MockBus(wires.clone()),
+    )
+    .with_clock(now_us);
+
+    let clear = Clear::compile().into();
+    assert!(pins.write(&mut NoDelay, clear).is_ok());
+    // Polls do not count towards the wait, only the clock does, across its wrap-around.
+    NOW_US.set(now_us().wrapping_add(Clear::EXECUTION_TIME_US - 1));
+    for _ in 0..20 {
+        assert!(matches!(
+            pins.write(&mut NoDelay, clear),
+            Err(nb::Error::WouldBlock)
+        ));
+    }
+    NOW_US.set(now_us().wrapping_add(1));
+    assert!(pins.write(&mut NoDelay, clear).is_ok());
+    assert_eq!(writes(&wires).len(), 4);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn critical_section_mutex_shares_bus() {
+    let wires = Shared::default();
+    let bus = critical_section::Mutex::new(RefCell::new(BusLines::new(
+        MockPin(wires.clone(), Line::RegisterSelection),
+        Grounded,
+        MockBus(wires.clone()),
+    )));
+    let mut first = SharedLcdPins::new(&bus, MockPin(wires.clone(), Line::Enable));
+    let mut second = SharedLcdPins::new(&bus, MockPin(wires.clone(), Line::Enable));
+
+    assert!(first.write(&mut NoDelay, Deliverable::Data(b'A')).is_ok());
+    assert!(second.write(&mut NoDelay, Deliverable::Data(b'B')).is_ok());
+    let sent: Vec<_> = writes(&wires).iter().map(|p| p.nibble).collect();
+    assert_eq!(sent, [0x4, 0x1, 0x4, 0x2]);
+}
+
+#[test]
+fn trace_exports_vcd_with_delay_timestamps() {
+    let wires = Shared::default();
+    let trace = RefCell::new(Trace::<16>::new());
+    let mut pins = LcdPins::new(
+        TracedPin::new(
+            MockPin(wires.clone(), Line::RegisterSelection),
+            &trace,
+            TraceLine::RegisterSelection,
+        ),
+        TracedPin::new(
+            MockPin(wires.clone(), Line::ReadWrite),
+            &trace,
+            TraceLine::ReadWrite,
+        ),
+        TracedPin::new(
+            MockPin(wires.clone(), Line::Enable),
+            &trace,
+            TraceLine::Enable,
+        ),
+        TracedBus::new(MockBus(wires.clone()), &trace),
+    );
+    let mut delay = TracedDelay::new(NoDelay, &trace);
+    assert!(pins.write_nibble(&mut delay, 0x3).is_ok());
+    assert_eq!(writes(&wires).len(), 1);
+
+    let mut vcd = String::new();
+    assert!(trace.borrow().write_vcd(&mut vcd).is_ok());
+    assert_eq!(
+        vcd,
+        "$timescale 1ns $end\n\
+         $scope module lcd $end\n\
+         $var wire 1 ! rs $end\n\
+         $var wire 1 \" rw $end\n\
+         $var wire 1 # e $end\n\
+         $var wire 4 $ db $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n$dumpvars\nx!\nx\"\nx#\nbx $\n$end\n\
+         0!\n0\"\nb0011 $\n\
+         #60\n1#\n\
+         #1060\n0#\n\
+         #2060\n"
+    );
+}